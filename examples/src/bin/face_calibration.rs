@@ -1,5 +1,6 @@
-//! This example flashes the pico onboard LED 3 times, then captures whatever face is present / largest
-//! as ID 0. The pico onboard LED will turn on when this face is recognized again.
+//! This example calibrates the face in front of the sensor as ID 0, retrying until exactly one face
+//! is looking at the sensor and has been recognized after labeling. The pico onboard LED blinks
+//! while calibrating, then turns on whenever this face is recognized again.
 
 #![no_std]
 #![no_main]
@@ -11,8 +12,11 @@ use embassy_rp::{
     i2c::{self, Config, I2c},
     peripherals::I2C1,
 };
-use embassy_time::Timer;
-use person_sensor::PersonSensorBuilder;
+use embassy_time::{Delay, Timer};
+use person_sensor::{
+    calibration::{CalibrationConfig, CalibrationError},
+    PersonSensorBuilder, Recognition,
+};
 use {defmt_rtt as _, panic_probe as _};
 
 bind_interrupts!(struct Irqs {
@@ -28,43 +32,45 @@ async fn main(_spawner: Spawner) {
     let scl = p.PIN_3;
     let i2c = I2c::new_async(p.I2C1, scl, sda, Irqs, Config::default());
 
-    // Create a sensor instance without an interrupt, initialized in standby mode, with the ID
+    // Create a sensor instance without an interrupt, initialized in continuous mode, with the ID
     // model enabled
-    let mut person_sensor = PersonSensorBuilder::new_standby(i2c, true)
+    let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, true)
         .build()
         .await
         .unwrap();
 
-    let mut led = Output::new(p.PIN_25, Level::High);
-
-    // Blink the LED 3 times before attempting to capture a face
-    for _ in 0..3 {
-        led.set_high();
-        Timer::after_millis(800).await;
+    let mut led = Output::new(p.PIN_25, Level::Low);
+    let id = 0.try_into().unwrap();
+    let config = CalibrationConfig::default();
 
-        led.set_low();
-        Timer::after_millis(800).await;
+    // Blink the LED while waiting for a single face looking at the sensor to be calibrated
+    loop {
+        led.toggle();
+        match person_sensor.calibrate(id, &config, &mut Delay).await {
+            Ok(()) => break,
+            Err(CalibrationError::NoFace) => defmt::info!("Waiting for a face"),
+            Err(CalibrationError::MultipleFaces) => defmt::info!("Too many faces"),
+            Err(CalibrationError::NotFacing) => defmt::info!("Look at the sensor"),
+            Err(CalibrationError::VerificationFailed) => defmt::info!("Face not recognized"),
+            Err(CalibrationError::ReadError(e)) => defmt::warn!("Error reading the sensor: {}", e),
+        }
+        Timer::after_millis(400).await;
     }
 
-    led.set_high();
-
-    Timer::after_millis(1600).await;
-
-    person_sensor
-        .label_next_id(0.try_into().unwrap())
-        .await
-        .unwrap();
-
+    defmt::info!("Calibration complete");
     led.set_low();
 
-    // Convert the sensor to continuous mode
-    let mut person_sensor = person_sensor.into_continuous_mode().await.unwrap();
-
     // Repeatedly loop in continuous capture mode
     // The pico LED will turn on in sync with the sensor LED when the calibrated face is detected
     loop {
         if let Ok(faces) = person_sensor.get_detections().await {
-            if !faces.is_empty() && faces.iter().any(|face| face.id_confidence > 90) {
+            let recognized = faces.iter().any(|face| {
+                matches!(
+                    face.recognition,
+                    Recognition::Identified { confidence, .. } if confidence > 90
+                )
+            });
+            if recognized {
                 led.set_high();
             } else {
                 led.set_low();
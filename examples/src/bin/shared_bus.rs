@@ -0,0 +1,57 @@
+//! This example shares I2C1 between the Person Sensor and an MPU-6050 IMU
+//! The IMU is connected to the same pins as the sensor, at its default address
+
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use embassy_embedded_hal::shared_bus::asynch::i2c::I2cDevice;
+use embassy_executor::Spawner;
+use embassy_rp::{
+    bind_interrupts,
+    i2c::{self, Config, I2c},
+    peripherals::I2C1,
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, mutex::Mutex};
+use embassy_time::Timer;
+use embedded_hal_async::i2c::I2c as _;
+use person_sensor::PersonSensorBuilder;
+
+use {defmt_rtt as _, panic_probe as _};
+bind_interrupts!(struct Irqs {
+    I2C1_IRQ => i2c::InterruptHandler<I2C1>;
+});
+
+const IMU_ADDRESS: u8 = 0x68;
+const IMU_WHO_AM_I: u8 = 0x75;
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_rp::init(Default::default());
+
+    // Set up I2C1 on pins 2 and 3, shared by every device on the bus
+    let sda = p.PIN_2;
+    let scl = p.PIN_3;
+    let i2c = I2c::new_async(p.I2C1, scl, sda, Irqs, Config::default());
+    let bus = Mutex::<NoopRawMutex, _>::new(i2c);
+
+    // Each device gets its own handle, which locks the bus for each transaction
+    let mut person_sensor = PersonSensorBuilder::new_continuous(I2cDevice::new(&bus), false)
+        .build()
+        .await
+        .unwrap();
+    let mut imu = I2cDevice::new(&bus);
+
+    let mut who_am_i = [0u8];
+    imu.write_read(IMU_ADDRESS, &[IMU_WHO_AM_I], &mut who_am_i)
+        .await
+        .unwrap();
+    info!("IMU id: {:#x}", who_am_i[0]);
+
+    loop {
+        if let Ok(faces) = person_sensor.get_detections().await {
+            info!("{} faces", faces.len());
+        }
+        Timer::after_millis(200).await;
+    }
+}
@@ -63,7 +63,7 @@ async fn main(spawner: Spawner) {
                 let size_x = face.box_right - face.box_left;
                 let size_y = face.box_bottom - face.box_top;
 
-                match face.id {
+                match face.id() {
                     Some(id) => log::info!(
                         "Person {} - x:{}, y:{} - {}x{}",
                         u8::from(id),
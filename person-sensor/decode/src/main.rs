@@ -0,0 +1,150 @@
+//! Decodes a captured stream of wire frames into CSV or JSON lines.
+//!
+//! ```bash
+//! person-sensor-decode [--csv | --json] [FILE]
+//! ```
+//!
+//! Reads from stdin if no file is given. CSV output has one row per face, and frames without
+//! faces are omitted. JSON output has one object per frame. Frames that fail to decode are
+//! reported on stderr and skipped. The ID confidence of faces that weren't identified is 0.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    process::ExitCode,
+};
+
+use person_sensor::{
+    wire::{StreamDecoder, WireFrame},
+    Face, Recognition,
+};
+
+enum Format {
+    Csv,
+    Json,
+}
+
+fn main() -> ExitCode {
+    let mut format = Format::Csv;
+    let mut path = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => format = Format::Json,
+            "--csv" => format = Format::Csv,
+            "-h" | "--help" => {
+                println!("Usage: person-sensor-decode [--csv | --json] [FILE]");
+                return ExitCode::SUCCESS;
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => {
+                eprintln!("Unexpected argument: {arg}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let input: Box<dyn Read> = match path {
+        Some(path) => match File::open(&path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                eprintln!("Failed to open {path}: {e}");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Box::new(io::stdin().lock()),
+    };
+
+    match run(input, &mut BufWriter::new(io::stdout().lock()), format) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(input: impl Read, output: &mut impl Write, format: Format) -> io::Result<()> {
+    if let Format::Csv = format {
+        writeln!(
+            output,
+            "timestamp_ms,face,box_confidence,left,top,right,bottom,id_confidence,id,is_facing"
+        )?;
+    }
+
+    // The stream is assumed to start at a frame boundary
+    let mut decoder = StreamDecoder::new();
+    decoder.push(0);
+
+    for byte in BufReader::new(input).bytes() {
+        match decoder.push(byte?) {
+            Some(Ok(frame)) => match format {
+                Format::Csv => write_csv(output, &frame)?,
+                Format::Json => write_json(output, &frame)?,
+            },
+            Some(Err(e)) => eprintln!("Skipping invalid frame: {e:?}"),
+            None => {}
+        }
+    }
+    output.flush()
+}
+
+fn write_csv(output: &mut impl Write, frame: &WireFrame) -> io::Result<()> {
+    for (i, face) in frame.faces.iter().enumerate() {
+        let id = face
+            .id()
+            .map(|id| u8::from(id).to_string())
+            .unwrap_or_default();
+        writeln!(
+            output,
+            "{},{},{},{},{},{},{},{},{},{}",
+            frame.timestamp_ms,
+            i,
+            face.box_confidence,
+            face.box_left,
+            face.box_top,
+            face.box_right,
+            face.box_bottom,
+            id_confidence(face),
+            id,
+            face.is_facing,
+        )?;
+    }
+    Ok(())
+}
+
+fn write_json(output: &mut impl Write, frame: &WireFrame) -> io::Result<()> {
+    let faces: Vec<String> = frame
+        .faces
+        .iter()
+        .map(|face| {
+            let id = face
+                .id()
+                .map(|id| u8::from(id).to_string())
+                .unwrap_or_else(|| "null".into());
+            format!(
+                r#"{{"box_confidence":{},"left":{},"top":{},"right":{},"bottom":{},"id_confidence":{},"id":{},"is_facing":{}}}"#,
+                face.box_confidence,
+                face.box_left,
+                face.box_top,
+                face.box_right,
+                face.box_bottom,
+                id_confidence(face),
+                id,
+                face.is_facing,
+            )
+        })
+        .collect();
+    writeln!(
+        output,
+        r#"{{"timestamp_ms":{},"faces":[{}]}}"#,
+        frame.timestamp_ms,
+        faces.join(",")
+    )
+}
+
+fn id_confidence(face: &Face) -> u8 {
+    match face.recognition {
+        Recognition::Identified { confidence, .. } => confidence,
+        _ => 0,
+    }
+}
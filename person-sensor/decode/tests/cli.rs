@@ -0,0 +1,92 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use person_sensor::{
+    wire::{self, WireFrame, MAX_ENCODED_LEN},
+    Detections, Face, PersonID, Recognition,
+};
+
+fn face(left: u8, recognition: Recognition, is_facing: bool) -> Face {
+    Face {
+        box_confidence: 90,
+        box_left: left,
+        box_top: 0,
+        box_right: left + 20,
+        box_bottom: 20,
+        recognition,
+        is_facing,
+    }
+}
+
+fn encode(frame: &WireFrame) -> Vec<u8> {
+    let mut buffer = [0u8; MAX_ENCODED_LEN];
+    let len = wire::encode(frame, &mut buffer).unwrap();
+    buffer[..len].to_vec()
+}
+
+fn run_decoder(args: &[&str], input: &[u8]) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_person-sensor-decode"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn identified() -> Face {
+    face(
+        0,
+        Recognition::Identified {
+            id: PersonID::new(2).unwrap(),
+            confidence: 77,
+        },
+        true,
+    )
+}
+
+#[test]
+fn csv() {
+    let frame = WireFrame {
+        timestamp_ms: 1234,
+        faces: Detections::from_slice(&[
+            identified(),
+            face(30, Recognition::NotLargestFace, false),
+        ])
+        .unwrap(),
+    };
+    let output = run_decoder(&[], &encode(&frame));
+    assert_eq!(
+        output,
+        "timestamp_ms,face,box_confidence,left,top,right,bottom,id_confidence,id,is_facing\n\
+         1234,0,90,0,0,20,20,77,2,true\n\
+         1234,1,90,30,0,50,20,0,,false\n"
+    );
+}
+
+#[test]
+fn json() {
+    let frame = WireFrame {
+        timestamp_ms: 1234,
+        faces: Detections::from_slice(&[identified()]).unwrap(),
+    };
+    let mut input = encode(&frame);
+    input.extend([0x01, 0x02, 0x00]);
+    input.extend(encode(&WireFrame {
+        timestamp_ms: 1300,
+        faces: Detections::new(),
+    }));
+
+    let output = run_decoder(&["--json"], &input);
+    assert_eq!(
+        output,
+        "{\"timestamp_ms\":1234,\"faces\":[{\"box_confidence\":90,\"left\":0,\"top\":0,\"right\":20,\"bottom\":20,\"id_confidence\":77,\"id\":2,\"is_facing\":true}]}\n\
+         {\"timestamp_ms\":1300,\"faces\":[]}\n"
+    );
+}
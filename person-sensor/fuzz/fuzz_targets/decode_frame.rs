@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use person_sensor::ReadError;
+use person_sensor_fuzz::{decode, frame};
+
+fuzz_target!(|data: &[u8]| {
+    let frame = frame(data);
+    match decode(frame) {
+        Ok(faces) => assert_eq!(faces.len(), usize::from(frame[4]).min(4)),
+        Err(error) => assert_eq!(error, ReadError::ChecksumMismatch),
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use person_sensor_fuzz::{decode, frame, with_checksum};
+
+fuzz_target!(|data: &[u8]| {
+    let frame = with_checksum(frame(data));
+    let faces = decode(frame).unwrap();
+    assert_eq!(faces.len(), usize::from(frame[4]).min(4));
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use person_sensor::wire::{self, StreamDecoder, MAX_ENCODED_LEN};
+
+fuzz_target!(|data: &[u8]| {
+    // Anything that decodes must encode back to the same frame
+    if let Ok(frame) = wire::decode(data) {
+        let mut buffer = [0u8; MAX_ENCODED_LEN];
+        let len = wire::encode(&frame, &mut buffer).unwrap();
+        assert_eq!(wire::decode(&buffer[..len]), Ok(frame));
+    }
+
+    let mut decoder = StreamDecoder::new();
+    for byte in data {
+        _ = decoder.push(*byte);
+    }
+});
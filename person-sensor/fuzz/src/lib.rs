@@ -0,0 +1,89 @@
+//! Helpers shared by the fuzz targets.
+//!
+//! Run a target with `cargo fuzz run <target>` from the `person-sensor` directory.
+
+use core::{
+    convert::Infallible,
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use embedded_hal_async::i2c::{ErrorType, I2c, Operation, SevenBitAddress};
+use person_sensor::{encode_frame, Detections, PersonSensorBuilder, ReadError};
+
+/// A bus that returns the same frame for every read once configured, and accepts every write.
+/// Reads before the first write return an empty frame, so that the builder's probe succeeds.
+struct FrameBus {
+    frame: [u8; 39],
+    configured: bool,
+}
+
+impl ErrorType for FrameBus {
+    type Error = Infallible;
+}
+
+impl I2c<SevenBitAddress> for FrameBus {
+    async fn transaction(
+        &mut self,
+        _address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        for operation in operations {
+            match operation {
+                Operation::Read(buffer) => {
+                    let frame = if self.configured {
+                        self.frame
+                    } else {
+                        encode_frame(&[])
+                    };
+                    let len = buffer.len().min(frame.len());
+                    buffer[..len].copy_from_slice(&frame[..len]);
+                }
+                Operation::Write(_) => self.configured = true,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Polls a future that never waits to completion.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// The frame in the first 39 bytes of `data`, padded with zeros.
+pub fn frame(data: &[u8]) -> [u8; 39] {
+    let mut frame = [0u8; 39];
+    let len = data.len().min(frame.len());
+    frame[..len].copy_from_slice(&data[..len]);
+    frame
+}
+
+/// Replaces the checksum of `frame` with a valid one.
+pub fn with_checksum(mut frame: [u8; 39]) -> [u8; 39] {
+    let checksum = crc16::State::<crc16::MCRF4XX>::calculate(&frame[..37]);
+    frame[37..].copy_from_slice(&checksum.to_le_bytes());
+    frame
+}
+
+/// Reads `frame` through the driver, as if it had been returned by the sensor.
+pub fn decode(frame: [u8; 39]) -> Result<Detections, ReadError<Infallible>> {
+    block_on(async {
+        let bus = FrameBus {
+            frame,
+            configured: false,
+        };
+        let mut person_sensor = PersonSensorBuilder::new_continuous(bus, true)
+            .build()
+            .await
+            .expect("the probe frame is valid");
+        person_sensor.get_detections().await
+    })
+}
@@ -0,0 +1,164 @@
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+
+use crate::{
+    person_sensor::{ContinuousCaptureMode, StandbyMode},
+    Face, PersonID, PersonSensor, ReadError, Recognition, MAX_DETECTIONS,
+};
+
+/// Configuration for [`PersonSensor::calibrate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CalibrationConfig {
+    /// Faces with a lower box confidence are ignored, as if they weren't in the frame.
+    pub min_box_confidence: u8,
+    /// The maximum number of frames read after labeling to verify the calibration.
+    pub verification_frames: u8,
+    /// How many of the verification frames must recognize the face as the requested ID.
+    pub required_matches: u8,
+    /// The minimum ID confidence for a frame to count as a match.
+    pub min_id_confidence: u8,
+    /// Time between frames in continuous mode. In standby mode, each frame is captured and waited
+    /// for as configured by [`CaptureWait`](crate::CaptureWait).
+    pub frame_interval_ms: u32,
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        Self {
+            min_box_confidence: 60,
+            verification_frames: 10,
+            required_matches: 3,
+            min_id_confidence: 60,
+            frame_interval_ms: 200,
+        }
+    }
+}
+
+/// Reasons a calibration can fail.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CalibrationError<E> {
+    /// No face was found with sufficient confidence.
+    NoFace,
+    /// More than one face was found, so it is ambiguous which one would be labeled.
+    MultipleFaces,
+    /// The face was not looking at the sensor.
+    NotFacing,
+    /// The face was labeled, but was not recognized as the requested ID afterwards.
+    VerificationFailed,
+    /// Reading detections from the sensor failed.
+    ReadError(ReadError<E>),
+}
+
+impl<E> From<ReadError<E>> for CalibrationError<E> {
+    fn from(error: ReadError<E>) -> Self {
+        Self::ReadError(error)
+    }
+}
+
+impl<I2C, INT> PersonSensor<I2C, INT, ContinuousCaptureMode>
+where
+    I2C: I2c,
+{
+    /// Calibrate the face in view as person `id`, and verify it is recognized afterwards.
+    ///
+    /// Exactly one confident face that is looking at the sensor must be present, otherwise no
+    /// label is assigned. Once labeled, up to `verification_frames` frames are read until the face
+    /// has been recognized as `id` in `required_matches` of them.
+    ///
+    /// > Note: a failed verification does not undo the label on the sensor.
+    pub async fn calibrate<D: DelayNs>(
+        &mut self,
+        id: PersonID,
+        config: &CalibrationConfig,
+        delay: &mut D,
+    ) -> Result<(), CalibrationError<I2C::Error>> {
+        self.run_calibration(id, config, delay, false).await
+    }
+}
+
+impl<I2C, INT> PersonSensor<I2C, INT, StandbyMode>
+where
+    I2C: I2c,
+{
+    /// Calibrate the face in view as person `id`, and verify it is recognized afterwards.
+    ///
+    /// Exactly one confident face that is looking at the sensor must be present, otherwise no
+    /// label is assigned. Once labeled, up to `verification_frames` frames are captured until the
+    /// face has been recognized as `id` in `required_matches` of them.
+    ///
+    /// > Note: a failed verification does not undo the label on the sensor.
+    pub async fn calibrate<D: DelayNs>(
+        &mut self,
+        id: PersonID,
+        config: &CalibrationConfig,
+        delay: &mut D,
+    ) -> Result<(), CalibrationError<I2C::Error>> {
+        self.run_calibration(id, config, delay, true).await
+    }
+}
+
+impl<I2C, INT, MODE> PersonSensor<I2C, INT, MODE>
+where
+    I2C: I2c,
+{
+    async fn run_calibration<D: DelayNs>(
+        &mut self,
+        id: PersonID,
+        config: &CalibrationConfig,
+        delay: &mut D,
+        standby: bool,
+    ) -> Result<(), CalibrationError<I2C::Error>> {
+        let faces = self.next_frame(config, delay, standby).await?;
+        let mut confident = faces
+            .iter()
+            .filter(|face| face.box_confidence >= config.min_box_confidence);
+        let face = match (confident.next(), confident.next()) {
+            (None, _) => return Err(CalibrationError::NoFace),
+            (Some(_), Some(_)) => return Err(CalibrationError::MultipleFaces),
+            (Some(face), None) => face,
+        };
+        if !face.is_facing {
+            return Err(CalibrationError::NotFacing);
+        }
+
+        self.label_next_id(id)
+            .await
+            .map_err(|e| CalibrationError::ReadError(ReadError::I2CError(e)))?;
+
+        let mut matches = 0;
+        for _ in 0..config.verification_frames {
+            let faces = self.next_frame(config, delay, standby).await?;
+            let recognized = faces.iter().any(|face| {
+                face.box_confidence >= config.min_box_confidence
+                    && matches!(
+                        face.recognition,
+                        Recognition::Identified { id: recognized, confidence }
+                            if recognized == id && confidence >= config.min_id_confidence
+                    )
+            });
+            if recognized {
+                matches += 1;
+                if matches >= config.required_matches {
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(CalibrationError::VerificationFailed)
+    }
+
+    async fn next_frame<D: DelayNs>(
+        &mut self,
+        config: &CalibrationConfig,
+        delay: &mut D,
+        standby: bool,
+    ) -> Result<heapless::Vec<Face, MAX_DETECTIONS>, ReadError<I2C::Error>> {
+        if standby {
+            self.capture(delay).await
+        } else {
+            delay.delay_ms(config.frame_interval_ms).await;
+            self.latest_results().await
+        }
+    }
+}
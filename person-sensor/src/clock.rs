@@ -0,0 +1,21 @@
+/// A monotonic source of time, in milliseconds.
+///
+/// Any `Fn() -> u64` closure is a clock, which makes it easy to wrap a HAL timer or to drive
+/// time manually in tests:
+///
+/// ```ignore
+/// let clock = || embassy_time::Instant::now().as_millis();
+/// ```
+pub trait Clock {
+    /// Milliseconds elapsed since an arbitrary, fixed starting point.
+    fn now_ms(&self) -> u64;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> u64,
+{
+    fn now_ms(&self) -> u64 {
+        self()
+    }
+}
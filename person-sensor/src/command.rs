@@ -0,0 +1,124 @@
+//! Typed commands for controlling the sensor from other tasks, or remotely.
+//!
+//! A [`Command`] describes a single write to the sensor. Commands are small and `Copy`, so they
+//! can be sent through any channel to the task that owns a [`CommandExecutor`]. With the `serde`
+//! feature they can also be serialized, for example with postcard, to control the sensor over
+//! UART.
+//!
+//! Example:
+//! ```ignore
+//! let sensor = PersonSensorBuilder::new_continuous(i2c, true).build().await.unwrap();
+//! let mut executor = CommandExecutor::new(sensor);
+//!
+//! loop {
+//!     let command = COMMANDS.receive().await;
+//!     let result = executor.execute(command, &mut Delay).await;
+//!     RESULTS.send(result).await;
+//! }
+//! ```
+
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+
+use crate::{
+    power::ManagedSensor, ContinuousCaptureMode, Detections, PersonID, PersonSensor,
+    PersonSensorMode, ReadError, StandbyMode,
+};
+
+/// A request to change the configuration of the sensor, or to read it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Command {
+    /// Switch between standby and continuous capture.
+    SetMode(PersonSensorMode),
+    /// Enable or disable the ID model.
+    EnableIdModel(bool),
+    /// Label the next identified face. See [`PersonSensor::label_next_id`].
+    LabelNextId(PersonID),
+    /// Store recognized IDs even when unpowered.
+    SetPersistIds(bool),
+    /// Wipe any recognized IDs from storage.
+    EraseIds,
+    /// Enable or disable the LED indicator on the sensor.
+    SetIndicator(bool),
+    /// Read the detections, capturing a single frame first while in standby.
+    CaptureOnce,
+}
+
+/// Applies [`Command`]s to a sensor it owns, keeping track of the mode the sensor is in.
+#[derive(Debug)]
+pub struct CommandExecutor<I2C, INT> {
+    pub(crate) sensor: PersonSensor<I2C, INT, ()>,
+    mode: PersonSensorMode,
+}
+
+impl<I2C, INT> CommandExecutor<I2C, INT>
+where
+    I2C: I2c,
+{
+    /// Create an executor from a sensor in continuous mode.
+    pub fn new(sensor: PersonSensor<I2C, INT, ContinuousCaptureMode>) -> Self {
+        Self {
+            sensor: sensor.into_mode(),
+            mode: PersonSensorMode::Continuous,
+        }
+    }
+
+    /// Create an executor from a sensor in standby mode.
+    pub fn from_standby(sensor: PersonSensor<I2C, INT, StandbyMode>) -> Self {
+        Self {
+            sensor: sensor.into_mode(),
+            mode: PersonSensorMode::Standby,
+        }
+    }
+
+    /// The mode the sensor is currently in.
+    pub fn mode(&self) -> PersonSensorMode {
+        self.mode
+    }
+
+    /// Apply a command to the sensor. Returns the detections for [`Command::CaptureOnce`], and
+    /// `None` for every other command.
+    ///
+    /// A single-shot capture waits as configured by [`CaptureWait`](crate::CaptureWait). In
+    /// continuous mode the latest detections are read instead. A mode change that fails leaves
+    /// the tracked mode unchanged.
+    pub async fn execute<D: DelayNs>(
+        &mut self,
+        command: Command,
+        delay: &mut D,
+    ) -> Result<Option<Detections>, ReadError<I2C::Error>> {
+        match command {
+            Command::SetMode(mode) => {
+                self.sensor.set_mode(mode).await?;
+                self.mode = mode;
+            }
+            Command::EnableIdModel(enable) => self.sensor.enable_id_model(enable).await?,
+            Command::LabelNextId(id) => self.sensor.label_next_id(id).await?,
+            Command::SetPersistIds(persist) => self.sensor.set_persist_ids(persist).await?,
+            Command::EraseIds => self.sensor.erase_ids().await?,
+            Command::SetIndicator(enabled) => self.sensor.set_indicator(enabled).await?,
+            Command::CaptureOnce => {
+                let detections = match self.mode {
+                    PersonSensorMode::Standby => self.sensor.capture(delay).await?,
+                    PersonSensorMode::Continuous => self.sensor.latest_results().await?,
+                };
+                return Ok(Some(detections));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Read the latest detections without capturing.
+    pub async fn latest_results(&mut self) -> Result<Detections, ReadError<I2C::Error>> {
+        self.sensor.latest_results().await
+    }
+
+    /// Stop executing commands and return the sensor in its current mode.
+    pub fn release(self) -> ManagedSensor<I2C, INT> {
+        match self.mode {
+            PersonSensorMode::Standby => ManagedSensor::Standby(self.sensor.into_mode()),
+            PersonSensorMode::Continuous => ManagedSensor::Continuous(self.sensor.into_mode()),
+        }
+    }
+}
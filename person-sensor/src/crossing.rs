@@ -0,0 +1,169 @@
+//! Counting people as they cross a line in the sensor's view.
+//!
+//! Example:
+//! ```ignore
+//! // A line across the middle of the view. People walking down across it are counted as in.
+//! let line = Line { start: (0, 128), end: (255, 128) };
+//! let mut counter = LineCounter::<4>::new(line, TrackerConfig::default());
+//!
+//! loop {
+//!     let faces = person_sensor.get_detections().await?;
+//!     counter.update(&faces);
+//!     display.show(counter.count(Direction::In), counter.count(Direction::Out));
+//! }
+//! ```
+
+use crate::{
+    tracker::{TrackId, Tracker, TrackerConfig},
+    zone::Point,
+    Face, MAX_DETECTIONS,
+};
+
+/// A line segment in sensor coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Line {
+    pub start: Point,
+    pub end: Point,
+}
+
+impl Line {
+    /// Which side of the line `point` is on: positive to the right when looking from `start` to
+    /// `end`, negative to the left, and 0 on the line.
+    fn side(&self, point: Point) -> i32 {
+        orientation(self.start, self.end, point)
+    }
+}
+
+/// The direction a person crossed a [`Line`] in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    /// From the left of the line to its right, looking from its start to its end.
+    In,
+    /// From the right of the line to its left, looking from its start to its end.
+    Out,
+}
+
+/// A person crossing the line, reported by the [`LineCounter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Crossing {
+    pub track: TrackId,
+    pub direction: Direction,
+}
+
+/// Counts the people whose face crosses a line, in each direction.
+///
+/// Faces are followed across frames with a [`Tracker`] of up to `TRACKS` tracks, and a person is
+/// counted when the center of their face moves from one side of the line to the other between
+/// the ends of the line. A face that stops on the line is only counted once it leaves it, on the
+/// other side.
+#[derive(Debug, Clone)]
+pub struct LineCounter<const TRACKS: usize> {
+    line: Line,
+    tracker: Tracker<TRACKS>,
+    /// The last position of each track that wasn't on the line.
+    positions: heapless::Vec<(TrackId, Point), TRACKS>,
+    count_in: u32,
+    count_out: u32,
+}
+
+impl<const TRACKS: usize> LineCounter<TRACKS> {
+    pub fn new(line: Line, config: TrackerConfig) -> Self {
+        Self {
+            line,
+            tracker: Tracker::new(config),
+            positions: heapless::Vec::new(),
+            count_in: 0,
+            count_out: 0,
+        }
+    }
+
+    pub fn line(&self) -> &Line {
+        &self.line
+    }
+
+    /// The tracker following the faces.
+    pub fn tracker(&self) -> &Tracker<TRACKS> {
+        &self.tracker
+    }
+
+    /// The number of people who crossed the line in `direction` since the last reset.
+    pub fn count(&self, direction: Direction) -> u32 {
+        match direction {
+            Direction::In => self.count_in,
+            Direction::Out => self.count_out,
+        }
+    }
+
+    /// Follow the faces of the next frame, returning the people who crossed the line.
+    pub fn update(&mut self, faces: &[Face]) -> heapless::Vec<Crossing, MAX_DETECTIONS> {
+        for track in self.tracker.update(faces) {
+            self.positions.retain(|(id, _)| *id != track.id());
+        }
+
+        let mut crossings = heapless::Vec::new();
+        for track in self
+            .tracker
+            .tracks()
+            .iter()
+            .filter(|track| track.is_visible())
+        {
+            let current = track.center();
+            let side = self.line.side(current);
+            if side == 0 {
+                continue;
+            }
+
+            match self.positions.iter_mut().find(|(id, _)| *id == track.id()) {
+                Some((_, previous)) => {
+                    let crossed = self.line.side(*previous).signum() != side.signum()
+                        && orientation(*previous, current, self.line.start).signum()
+                            != orientation(*previous, current, self.line.end).signum();
+                    if crossed {
+                        let direction = if side > 0 {
+                            Direction::In
+                        } else {
+                            Direction::Out
+                        };
+                        // Can't fail, only visible tracks cross and there is one face for each
+                        _ = crossings.push(Crossing {
+                            track: track.id(),
+                            direction,
+                        });
+                    }
+                    *previous = current;
+                }
+                // Can't fail, there is at most one position for each track
+                None => _ = self.positions.push((track.id(), current)),
+            }
+        }
+
+        for crossing in &crossings {
+            match crossing.direction {
+                Direction::In => self.count_in = self.count_in.saturating_add(1),
+                Direction::Out => self.count_out = self.count_out.saturating_add(1),
+            }
+        }
+        crossings
+    }
+
+    /// Set both counts back to 0. People are still followed, so someone halfway across the line
+    /// is counted when they finish crossing.
+    pub fn reset(&mut self) {
+        self.count_in = 0;
+        self.count_out = 0;
+    }
+}
+
+/// Positive when `c` is to the right of the line from `a` to `b`, in sensor coordinates where
+/// `y` grows downwards.
+fn orientation(a: Point, b: Point, c: Point) -> i32 {
+    let (ax, ay) = (i32::from(a.0), i32::from(a.1));
+    let (bx, by) = (i32::from(b.0), i32::from(b.1));
+    let (cx, cy) = (i32::from(c.0), i32::from(c.1));
+    (bx - ax) * (cy - ay) - (by - ay) * (cx - ax)
+}
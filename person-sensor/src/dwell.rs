@@ -0,0 +1,277 @@
+//! How long people stay in view, and how long they look at the sensor.
+//!
+//! The [`DwellMonitor`] follows people across frames, timing each visit from the first frame a
+//! person is detected in to the last. Finished visits are collected into a [`DwellReport`] with
+//! histograms of the time spent in view and facing the sensor, which can be read and reset at the
+//! end of each reporting period.
+//!
+//! Example:
+//! ```ignore
+//! let clock = || Instant::now().as_millis();
+//! let mut monitor = DwellMonitor::<_, 4, 6>::new(clock, DwellConfig::default());
+//!
+//! loop {
+//!     let faces = person_sensor.get_detections().await?;
+//!     monitor.update(&faces);
+//!     if report_timer.expired() {
+//!         uplink.send(monitor.report()).await;
+//!         monitor.reset();
+//!     }
+//! }
+//! ```
+
+use crate::{
+    tracker::{TrackId, Tracker, TrackerConfig},
+    Clock, Face,
+};
+
+/// Counts durations in bins of equal width. The last bin also counts every longer duration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Histogram<const BINS: usize> {
+    bin_ms: u32,
+    counts: [u32; BINS],
+}
+
+impl<const BINS: usize> Histogram<BINS> {
+    pub fn new(bin_ms: u32) -> Self {
+        Self {
+            bin_ms,
+            counts: [0; BINS],
+        }
+    }
+
+    /// The width of each bin. Bin `i` counts durations from `i * bin_ms` up to, but excluding,
+    /// `(i + 1) * bin_ms`.
+    pub fn bin_ms(&self) -> u32 {
+        self.bin_ms
+    }
+
+    pub fn counts(&self) -> &[u32; BINS] {
+        &self.counts
+    }
+
+    /// The number of durations recorded.
+    pub fn total(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    pub fn record(&mut self, duration_ms: u32) {
+        let bin = duration_ms.checked_div(self.bin_ms).unwrap_or(u32::MAX);
+        let bin = usize::try_from(bin)
+            .unwrap_or(usize::MAX)
+            .min(BINS.saturating_sub(1));
+        if let Some(count) = self.counts.get_mut(bin) {
+            *count = count.saturating_add(1);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.counts = [0; BINS];
+    }
+}
+
+/// The time a person spent in view, reported by the [`DwellMonitor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Visit {
+    pub track: TrackId,
+    /// The time from the first to the last frame the person was detected in.
+    pub dwell_ms: u32,
+    /// The part of the dwell time the person spent facing the sensor.
+    pub facing_ms: u32,
+}
+
+/// The visits that finished during a reporting period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DwellReport<const BINS: usize> {
+    /// The number of visits.
+    pub visits: u32,
+    /// The dwell time of all visits together.
+    pub dwell_ms: u64,
+    /// The facing time of all visits together.
+    pub facing_ms: u64,
+    pub dwell: Histogram<BINS>,
+    pub facing: Histogram<BINS>,
+}
+
+impl<const BINS: usize> DwellReport<BINS> {
+    fn new(bin_ms: u32) -> Self {
+        Self {
+            visits: 0,
+            dwell_ms: 0,
+            facing_ms: 0,
+            dwell: Histogram::new(bin_ms),
+            facing: Histogram::new(bin_ms),
+        }
+    }
+
+    fn record(&mut self, visit: &Visit) {
+        self.visits = self.visits.saturating_add(1);
+        self.dwell_ms = self.dwell_ms.saturating_add(u64::from(visit.dwell_ms));
+        self.facing_ms = self.facing_ms.saturating_add(u64::from(visit.facing_ms));
+        self.dwell.record(visit.dwell_ms);
+        self.facing.record(visit.facing_ms);
+    }
+}
+
+/// Configuration for the [`DwellMonitor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DwellConfig {
+    pub tracker: TrackerConfig,
+    /// The width of each bin of the histograms.
+    pub bin_ms: u32,
+}
+
+impl Default for DwellConfig {
+    fn default() -> Self {
+        Self {
+            tracker: TrackerConfig::default(),
+            bin_ms: 5_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Presence {
+    track: TrackId,
+    first_seen_ms: u64,
+    last_seen_ms: u64,
+    facing_ms: u32,
+    /// Whether the person was facing the sensor in the previous frame. Cleared when they are
+    /// missed, so the gap isn't counted.
+    facing: bool,
+}
+
+impl Presence {
+    fn visit(&self) -> Visit {
+        let dwell_ms = self.last_seen_ms.saturating_sub(self.first_seen_ms);
+        Visit {
+            track: self.track,
+            dwell_ms: u32::try_from(dwell_ms).unwrap_or(u32::MAX),
+            facing_ms: self.facing_ms,
+        }
+    }
+}
+
+/// Times the visits of up to `TRACKS` people at once, with histograms of `BINS` bins.
+///
+/// Time is read from `CLOCK` on every update. The time between two consecutive frames counts as
+/// facing the sensor when the person was detected facing it in the first of them. Frames the
+/// person was missed in count towards their dwell time, but never towards their facing time. A
+/// visit ends when its track is lost, and counts towards the period it ends in.
+#[derive(Debug, Clone)]
+pub struct DwellMonitor<CLOCK, const TRACKS: usize, const BINS: usize> {
+    clock: CLOCK,
+    tracker: Tracker<TRACKS>,
+    presences: heapless::Vec<Presence, TRACKS>,
+    report: DwellReport<BINS>,
+}
+
+impl<CLOCK, const TRACKS: usize, const BINS: usize> DwellMonitor<CLOCK, TRACKS, BINS>
+where
+    CLOCK: Clock,
+{
+    pub fn new(clock: CLOCK, config: DwellConfig) -> Self {
+        Self {
+            clock,
+            tracker: Tracker::new(config.tracker),
+            presences: heapless::Vec::new(),
+            report: DwellReport::new(config.bin_ms),
+        }
+    }
+
+    /// The tracker following the faces.
+    pub fn tracker(&self) -> &Tracker<TRACKS> {
+        &self.tracker
+    }
+
+    /// The visits that finished since the last reset.
+    pub fn report(&self) -> &DwellReport<BINS> {
+        &self.report
+    }
+
+    /// The visits still in progress, up to the last frame each person was detected in.
+    pub fn current(&self) -> impl Iterator<Item = Visit> + '_ {
+        self.presences.iter().map(Presence::visit)
+    }
+
+    /// Follow the faces of the next frame, returning the visits that finished.
+    pub fn update(&mut self, faces: &[Face]) -> heapless::Vec<Visit, TRACKS> {
+        let now = self.clock.now_ms();
+
+        let mut finished = heapless::Vec::new();
+        for track in self.tracker.update(faces) {
+            let Some(index) = self
+                .presences
+                .iter()
+                .position(|presence| presence.track == track.id())
+            else {
+                continue;
+            };
+            let visit = self.presences.swap_remove(index).visit();
+            self.report.record(&visit);
+            // Can't fail, there are at most as many lost tracks as tracks
+            _ = finished.push(visit);
+        }
+
+        for track in self
+            .tracker
+            .tracks()
+            .iter()
+            .filter(|track| track.is_visible())
+        {
+            let facing = track.face().is_facing;
+            match self
+                .presences
+                .iter_mut()
+                .find(|presence| presence.track == track.id())
+            {
+                Some(presence) => {
+                    if presence.facing {
+                        let elapsed = now.saturating_sub(presence.last_seen_ms);
+                        let elapsed = u32::try_from(elapsed).unwrap_or(u32::MAX);
+                        presence.facing_ms = presence.facing_ms.saturating_add(elapsed);
+                    }
+                    presence.last_seen_ms = now;
+                    presence.facing = facing;
+                }
+                // Can't fail, there is at most one presence for each track
+                None => {
+                    _ = self.presences.push(Presence {
+                        track: track.id(),
+                        first_seen_ms: now,
+                        last_seen_ms: now,
+                        facing_ms: 0,
+                        facing,
+                    })
+                }
+            }
+        }
+
+        for track in self
+            .tracker
+            .tracks()
+            .iter()
+            .filter(|track| !track.is_visible())
+        {
+            if let Some(presence) = self
+                .presences
+                .iter_mut()
+                .find(|presence| presence.track == track.id())
+            {
+                presence.facing = false;
+            }
+        }
+
+        finished
+    }
+
+    /// Start a new reporting period. Visits in progress carry on, and are reported in the period
+    /// they finish in.
+    pub fn reset(&mut self) {
+        self.report = DwellReport::new(self.report.dwell.bin_ms());
+    }
+}
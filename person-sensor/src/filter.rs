@@ -0,0 +1,130 @@
+//! Dropping detections that are likely to be false positives.
+//!
+//! A [`DetectionFilter`] is built up from any number of checks, and keeps the faces that pass all
+//! of them. Give it to the driver with
+//! [`PersonSensorBuilder::with_filter`](crate::PersonSensorBuilder::with_filter) to filter every
+//! frame as it is read, or [`apply`](DetectionFilter::apply) it to frames from elsewhere, such as
+//! a recording.
+//!
+//! Example:
+//! ```ignore
+//! // The TV in the corner of the room shows faces too
+//! let tv = Zone::rect(BoundingBox { left: 200, top: 0, right: 255, bottom: 60 }, 50);
+//! let filter = DetectionFilter::new()
+//!     .with_min_confidence(60)
+//!     .with_size(12, 200)
+//!     .with_max_aspect_ratio_percent(200)
+//!     .with_ignore_zone(tv)?;
+//!
+//! let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, true)
+//!     .with_filter(filter.clone())
+//!     .build()
+//!     .await?;
+//!
+//! // The same filter applied to frames captured with the `wire` module
+//! let mut frame = wire::decode(&encoded)?;
+//! filter.apply(&mut frame.faces);
+//! ```
+
+use crate::{
+    zone::{Zone, ZoneError},
+    Face,
+};
+
+/// The maximum number of zones a [`DetectionFilter`] can ignore.
+pub const MAX_IGNORE_ZONES: usize = 4;
+
+/// Keeps the faces that pass every configured check. A new filter keeps every face.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DetectionFilter {
+    min_confidence: u8,
+    min_size: u8,
+    max_size: u8,
+    facing_only: bool,
+    max_aspect_ratio_percent: Option<u16>,
+    ignore_zones: heapless::Vec<Zone, MAX_IGNORE_ZONES>,
+}
+
+impl Default for DetectionFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DetectionFilter {
+    pub const fn new() -> Self {
+        Self {
+            min_confidence: 0,
+            min_size: 0,
+            max_size: u8::MAX,
+            facing_only: false,
+            max_aspect_ratio_percent: None,
+            ignore_zones: heapless::Vec::new(),
+        }
+    }
+
+    /// Drops faces with a lower box confidence than `min_confidence`.
+    pub fn with_min_confidence(self, min_confidence: u8) -> Self {
+        Self {
+            min_confidence,
+            ..self
+        }
+    }
+
+    /// Drops faces narrower or shorter than `min`, or wider or taller than `max`. If `min` is
+    /// larger than `max`, the bounds are swapped.
+    pub fn with_size(self, min: u8, max: u8) -> Self {
+        Self {
+            min_size: min.min(max),
+            max_size: min.max(max),
+            ..self
+        }
+    }
+
+    /// Drops faces that aren't looking directly at the sensor.
+    pub fn with_facing_only(self, facing_only: bool) -> Self {
+        Self {
+            facing_only,
+            ..self
+        }
+    }
+
+    /// Drops faces whose longer side is more than `max_percent` percent of their shorter side. At
+    /// 200, a face can be up to twice as tall as it is wide, or the other way around.
+    pub fn with_max_aspect_ratio_percent(self, max_percent: u16) -> Self {
+        Self {
+            max_aspect_ratio_percent: Some(max_percent),
+            ..self
+        }
+    }
+
+    /// Drops faces inside `zone`, such as a poster or screen that shows faces.
+    pub fn with_ignore_zone(mut self, zone: Zone) -> Result<Self, ZoneError> {
+        self.ignore_zones.push(zone).map_err(|_| ZoneError::Full)?;
+        Ok(self)
+    }
+
+    /// Whether `face` passes every check.
+    pub fn keeps(&self, face: &Face) -> bool {
+        let bounding_box = face.bounding_box();
+        let (width, height) = (bounding_box.width(), bounding_box.height());
+        let size = self.min_size..=self.max_size;
+
+        face.box_confidence >= self.min_confidence
+            && size.contains(&width)
+            && size.contains(&height)
+            && (face.is_facing || !self.facing_only)
+            && self.max_aspect_ratio_percent.is_none_or(|max_percent| {
+                let (longer, shorter) = (width.max(height), width.min(height));
+                u32::from(longer) * 100 <= u32::from(shorter) * u32::from(max_percent)
+            })
+            && !self.ignore_zones.iter().any(|zone| zone.contains(face))
+    }
+
+    /// Removes the faces that don't pass every check, keeping the others in order.
+    pub fn apply<const N: usize>(&self, faces: &mut heapless::Vec<Face, N>) {
+        faces.retain(|face| self.keeps(face));
+    }
+}
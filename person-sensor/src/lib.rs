@@ -7,6 +7,10 @@
 //! This driver has been tested with v1.1 of the sensor, but should also work with v1 and v2.
 //! If you're able to validate the other board revisions, please open a pr to update this message :)
 //!
+//! The board revision can't be read from the sensor. It can be configured on the builder with
+//! `with_revision`, or estimated from the frame rate with `detect_revision` while somebody moves
+//! in front of the sensor.
+//!
 //! ## Usage
 //!
 //! The sensor offers two modes: continuous and single shot.
@@ -18,15 +22,18 @@
 //!
 //! let i2c = /* ... */;
 //! let interrupt_pin = /* ... */;
+//! let mut delay = /* ... */;
 //!
 //! // The driver can be initialized with or without the interrupt pin using the builder
+//! // It checks that the sensor responds first, and fails with ProbeError::NotDetected otherwise
 //! let mut person_sensor = PersonSensorBuilder::new_standby(i2c, true)
 //!     .with_interrupt(interrupt_pin) // optional
 //!     .build()
 //!     .await
 //!     .unwrap();
 //!
-//! let detections = person_sensor.capture_once().await.unwrap();
+//! // Single-shot captures wait for the sensor to finish before reading the results
+//! let detections = person_sensor.capture_once(&mut delay).await.unwrap();
 //!
 //! // ERROR: an interrupt pin was provided, but person_sensor is in standby mode
 //! // person_sensor.wait_for_person().await.unwrap();
@@ -42,6 +49,54 @@
 //! let detections = person_sensor.get_detections().await.unwrap();
 //! ```
 //!
+//! ## Sharing the bus
+//!
+//! The driver accepts any `embedded-hal-async` I2C bus, so it doesn't need to own the bus. It can
+//! borrow it instead, and an owned bus can be taken back with `release`:
+//!
+//! ```ignore
+//! let mut person_sensor = PersonSensorBuilder::new_continuous(&mut i2c, true)
+//!     .build()
+//!     .await
+//!     .unwrap();
+//! ```
+//!
+//! When the bus is shared with other devices, give the driver its own device handle. With embassy,
+//! use an `I2cDevice` from `embassy-embedded-hal` over a mutex:
+//!
+//! ```ignore
+//! let bus = Mutex::<NoopRawMutex, _>::new(i2c);
+//! let mut person_sensor = PersonSensorBuilder::new_continuous(I2cDevice::new(&bus), true)
+//!     .build()
+//!     .await
+//!     .unwrap();
+//! let mut imu = I2cDevice::new(&bus);
+//! ```
+//!
+//! Blocking shared buses from `embedded-hal-bus`, such as `RefCellDevice` and
+//! `CriticalSectionDevice`, can be used by wrapping the device in `BlockingAsync` from
+//! `embassy-embedded-hal`.
+//!
+//! ## Features
+//!
+//! - `serde`: implements `Serialize` and `Deserialize` for `Face`, `PersonID`, `BoundingBox`,
+//!   `Recognition` and frames of detections.
+//! - `defmt`: implements `defmt::Format` for all public data and error types.
+//! - `embassy`: a `service` task that shares detections with other tasks
+//!   through embassy-sync channels.
+//! - `sim`: a simulated sensor for testing code that uses the driver without hardware. Requires
+//!   `alloc`.
+//!
+//! ## Streaming to a host
+//!
+//! The [`wire`] module encodes timestamped detections into compact, checksummed frames that can be
+//! sent over UART or USB. The `person-sensor-decode` tool, in its own crate next to the driver,
+//! converts a captured stream back into CSV or JSON lines. From the `person-sensor` directory:
+//!
+//! ```bash
+//! cargo run --manifest-path decode/Cargo.toml -- --json capture.bin
+//! ```
+//!
 //! ## Examples
 //!
 //! To run the examples on a pi pico, it should be sufficient to enter bootloader mode and run:
@@ -53,18 +108,53 @@
 
 #![no_std]
 
+#[cfg(feature = "sim")]
+extern crate alloc;
+
+pub mod calibration;
+mod clock;
+pub mod command;
+pub mod crossing;
+pub mod dwell;
+pub mod filter;
 mod person_sensor;
 mod person_sensor_builder;
+pub mod power;
+mod probe;
+pub mod recognizer;
+pub mod record;
+pub mod registry;
+mod revision;
+pub mod select;
+#[cfg(feature = "embassy")]
+pub mod service;
+#[cfg(feature = "sim")]
+pub mod sim;
+pub mod smooth;
+pub mod tracker;
+pub mod wire;
+pub mod zone;
 
+pub use clock::Clock;
+pub use person_sensor::encode_frame;
 pub use person_sensor::PersonSensor;
 pub use person_sensor::ReadError;
+pub use person_sensor::{CaptureWait, ContinuousCaptureMode, PersonSensorMode, StandbyMode};
 pub use person_sensor_builder::PersonSensorBuilder;
+pub use probe::{probe, ProbeError};
+pub use revision::SensorRevision;
+pub use select::SelectFaces;
 
 /// The number of detections returned by the sensor.
-const MAX_DETECTIONS: usize = 4;
+pub const MAX_DETECTIONS: usize = 4;
+
+/// The detections from a single frame.
+pub type Detections = heapless::Vec<Face, MAX_DETECTIONS>;
 
 #[repr(C, packed)]
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Face {
     /// Confidence of the box prediction, ranges from 1 to 100.
     pub box_confidence: u8,
@@ -72,29 +162,192 @@ pub struct Face {
     pub box_top: u8,
     pub box_right: u8,
     pub box_bottom: u8,
-    /// The confidence of "calibrated" identities ranges from 1 to 100, and will be 0 or less if
-    /// this face is not recognized as any of the calibrated identities or is not the largest face
-    /// in the frame.
-    pub id_confidence: i8,
-    /// The ID number of the face, if it is recognized as any of the
-    /// calibrated identities *and* is the largest face in the frame.
+    /// Whether this face was recognized as one of the calibrated identities, and with which
+    /// confidence.
     /// By default, the sensor will not run any recognition until calibration has been performed.
-    /// After at least one person has been calibrated, the sensor will always run recognition on
-    /// the largest face present, and assign an ID number if it’s recognized as one that it has been calibrated on.
-    pub id: Option<PersonID>,
+    /// After at least one person has been calibrated, the sensor assigns an ID number to faces
+    /// it recognizes as one that it has been calibrated on. This isn't limited to the largest
+    /// face: smaller faces in the same frame can be identified too.
+    pub recognition: Recognition,
     /// Indicates if somebody is looking directly at the device
     /// > Note: ID works most reliably when the face is straight on to the sensor
     pub is_facing: bool,
 }
 
+impl Face {
+    /// The ID number of the face, if it is recognized as any of the calibrated identities.
+    pub fn id(&self) -> Option<PersonID> {
+        self.recognition.id()
+    }
+
+    /// The area of the bounding box. Boxes with inverted edges have no area.
+    pub fn area(&self) -> u16 {
+        self.bounding_box().area()
+    }
+
+    /// The bounding box of the face.
+    pub fn bounding_box(&self) -> BoundingBox {
+        BoundingBox {
+            left: self.box_left,
+            top: self.box_top,
+            right: self.box_right,
+            bottom: self.box_bottom,
+        }
+    }
+}
+
+/// A rectangle in sensor coordinates, which range from 0 to 255 on both axes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoundingBox {
+    pub left: u8,
+    pub top: u8,
+    pub right: u8,
+    pub bottom: u8,
+}
+
+impl BoundingBox {
+    /// The width of the box. Boxes with inverted edges have no width.
+    pub fn width(&self) -> u8 {
+        self.right.saturating_sub(self.left)
+    }
+
+    /// The height of the box. Boxes with inverted edges have no height.
+    pub fn height(&self) -> u8 {
+        self.bottom.saturating_sub(self.top)
+    }
+
+    /// The area of the box.
+    pub fn area(&self) -> u16 {
+        u16::from(self.width()) * u16::from(self.height())
+    }
+
+    /// The center of the box, as `(x, y)`.
+    pub fn center(&self) -> (u8, u8) {
+        let midpoint = |a: u8, b: u8| ((u16::from(a) + u16::from(b)) / 2) as u8;
+        (
+            midpoint(self.left, self.right),
+            midpoint(self.top, self.bottom),
+        )
+    }
+}
+
+/// The result of running recognition on a face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RawRecognition")
+)]
+pub enum Recognition {
+    /// The face is the largest in the frame, but was not recognized as a calibrated identity.
+    /// This is also reported if the sensor returns an ID outside of the valid range.
+    Unknown,
+    /// The face isn't the largest in the frame, and was not recognized as a calibrated identity,
+    /// i.e. the sensor reported a confidence of 0 or below for it.
+    ///
+    /// The largest face is inferred from the area of the bounding boxes when the frame is
+    /// decoded. This can disagree with the sensor, for example when two faces have the same area,
+    /// or when a box has inverted edges and is counted as having no area.
+    NotLargestFace,
+    /// The face was recognized as a calibrated identity, with a confidence from 1 to 100.
+    Identified { id: PersonID, confidence: u8 },
+}
+
+impl Recognition {
+    /// Interprets the raw ID fields reported by the sensor for a face.
+    ///
+    /// A positive confidence identifies the face only if `id` is a valid [`PersonID`]. Otherwise,
+    /// `is_largest` distinguishes faces the sensor did not recognize from those it did not try to.
+    pub fn from_raw(id: u8, confidence: i8, is_largest: bool) -> Self {
+        match (u8::try_from(confidence), PersonID::new(id)) {
+            (Ok(confidence @ 1..), Ok(id)) => Self::Identified { id, confidence },
+            (Ok(1..), Err(_)) => Self::Unknown,
+            _ if is_largest => Self::Unknown,
+            _ => Self::NotLargestFace,
+        }
+    }
+
+    /// A face recognized as `id`. Fails if `confidence` is 0, which the sensor reports for faces
+    /// it didn't recognize.
+    pub fn identified(id: PersonID, confidence: u8) -> Result<Self, RecognitionError> {
+        if confidence == 0 {
+            return Err(RecognitionError::ZeroConfidence);
+        }
+        Ok(Self::Identified { id, confidence })
+    }
+
+    /// The identified person, if any.
+    pub fn id(&self) -> Option<PersonID> {
+        match self {
+            Self::Identified { id, .. } => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+/// [`Recognition`] as it is serialized, before it is validated.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Recognition")]
+enum RawRecognition {
+    Unknown,
+    NotLargestFace,
+    Identified { id: PersonID, confidence: u8 },
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawRecognition> for Recognition {
+    type Error = RecognitionError;
+    fn try_from(raw: RawRecognition) -> Result<Self, Self::Error> {
+        match raw {
+            RawRecognition::Unknown => Ok(Self::Unknown),
+            RawRecognition::NotLargestFace => Ok(Self::NotLargestFace),
+            RawRecognition::Identified { id, confidence } => Self::identified(id, confidence),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RecognitionError {
+    /// Identified faces have a confidence of at least 1.
+    ZeroConfidence,
+}
+
+impl core::fmt::Display for RecognitionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::ZeroConfidence => f.write_str("identified faces have a confidence of at least 1"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PersonIDError {
     /// IDs can only range from 0 to 7.
     InvalidId,
 }
 
+impl core::fmt::Display for PersonIDError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidId => f.write_str("IDs can only range from 0 to 7"),
+        }
+    }
+}
+
 #[repr(transparent)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
 pub struct PersonID(u8);
 
 impl PersonID {
@@ -1,14 +1,23 @@
-use core::marker::PhantomData;
+use core::{
+    future::{poll_fn, Future},
+    marker::PhantomData,
+    pin::pin,
+    task::Poll,
+};
 
 use crc16::MCRF4XX;
-use embedded_hal_async::{digital::Wait, i2c::I2c};
+use embedded_hal_async::{delay::DelayNs, digital::Wait, i2c::I2c};
 
-use crate::{Face, PersonID, MAX_DETECTIONS};
+use crate::{filter::DetectionFilter, Face, PersonID, Recognition, SensorRevision, MAX_DETECTIONS};
 
-const PERSON_SENSOR_I2C_ADDRESS: u8 = 0x62;
+pub(crate) const PERSON_SENSOR_I2C_ADDRESS: u8 = 0x62;
 
+/// The capture mode of the sensor.
 #[repr(u8)]
-pub(crate) enum PersonSensorMode {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PersonSensorMode {
     /// Lowest power mode, sensor is in standby and not capturing.
     Standby = 0x00,
     /// Capture continuously, setting the GPIO trigger pin to high if a face is detected.
@@ -16,6 +25,7 @@ pub(crate) enum PersonSensorMode {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ReadError<E> {
     ChecksumMismatch,
     I2CError(E),
@@ -27,7 +37,34 @@ impl<E> From<E> for ReadError<E> {
     }
 }
 
+/// How a single-shot capture waits for the sensor to finish running inference.
+///
+/// The sensor does not signal when a requested capture is complete, so reading results
+/// immediately after requesting a capture returns the previous frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CaptureWait {
+    /// Wait a fixed amount of time before reading the results. This should be at least the
+    /// duration of one frame for the sensor and configuration in use.
+    Delay { ms: u32 },
+    /// Poll the sensor every `interval_ms` until the results differ from the frame that was
+    /// available before the capture was requested, then return them.
+    ///
+    /// If the scene is unchanged the results are identical to the previous frame, so this waits
+    /// the full `timeout_ms` before returning the latest results. An `interval_ms` of 0 is
+    /// treated as 1, so the sensor is never polled without a delay.
+    UntilChanged { interval_ms: u32, timeout_ms: u32 },
+}
+
+impl Default for CaptureWait {
+    fn default() -> Self {
+        Self::Delay { ms: 200 }
+    }
+}
+
+#[derive(Debug)]
 pub struct ContinuousCaptureMode;
+#[derive(Debug)]
 pub struct StandbyMode;
 
 /// The person sensor driver.
@@ -63,6 +100,111 @@ pub struct PersonSensor<I2C, INT, MODE> {
     pub(crate) i2c: I2C,
     pub(crate) interrupt: INT,
     pub(crate) mode: PhantomData<MODE>,
+    pub(crate) capture_wait: CaptureWait,
+    pub(crate) revision: SensorRevision,
+    pub(crate) filter: DetectionFilter,
+}
+
+/// Decodes and validates a raw frame read from the sensor.
+pub(crate) fn decode_frame<E>(
+    buffer: &[u8; 39],
+) -> Result<heapless::Vec<Face, MAX_DETECTIONS>, ReadError<E>> {
+    let checksum = crc16::State::<MCRF4XX>::calculate(&buffer[..37]);
+    if u16::from_le_bytes([buffer[37], buffer[38]]) != checksum {
+        return Err(ReadError::ChecksumMismatch);
+    }
+
+    let mut faces = heapless::Vec::<Face, MAX_DETECTIONS>::new();
+
+    // A corrupted count could otherwise read past the end of the frame
+    let num_faces = buffer[4].min(MAX_DETECTIONS as u8);
+    for face_num in 0..num_faces {
+        let face_start_offset = 5 + face_num as usize * 8;
+
+        let face = Face {
+            box_confidence: buffer[face_start_offset],
+            box_left: buffer[face_start_offset + 1],
+            box_top: buffer[face_start_offset + 2],
+            box_right: buffer[face_start_offset + 3],
+            box_bottom: buffer[face_start_offset + 4],
+            // Determined once all faces are known
+            recognition: Recognition::Unknown,
+            is_facing: buffer[face_start_offset + 7] > 0,
+        };
+
+        // The count is limited to the capacity above
+        _ = faces.push(face);
+    }
+
+    // The first of equally sized faces is considered the largest
+    let largest = faces
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, face)| face.area())
+        .map(|(i, _)| i);
+    for (i, face) in faces.iter_mut().enumerate() {
+        let confidence = buffer[5 + i * 8 + 5] as i8;
+        let id = buffer[5 + i * 8 + 6];
+        face.recognition = Recognition::from_raw(id, confidence, Some(i) == largest);
+    }
+
+    Ok(faces)
+}
+
+/// Encodes faces into a raw frame with a valid checksum, as the sensor would send them. Useful to
+/// build payloads for tests and simulations.
+///
+/// Identified faces are encoded with their ID and recognition confidence, which is limited to 127.
+/// Other faces are encoded with an ID and confidence of 0. Faces beyond [`MAX_DETECTIONS`] are
+/// dropped.
+///
+/// Example:
+/// ```
+/// # use person_sensor::{encode_frame, Face, Recognition};
+/// let frame = encode_frame(&[Face {
+///     box_confidence: 90,
+///     box_left: 100,
+///     box_top: 80,
+///     box_right: 150,
+///     box_bottom: 140,
+///     recognition: Recognition::Unknown,
+///     is_facing: true,
+/// }]);
+/// assert_eq!(frame[4], 1);
+/// ```
+pub fn encode_frame(faces: &[Face]) -> [u8; 39] {
+    let mut buffer = [0u8; 39];
+    buffer[2] = 0x21;
+    buffer[4] = faces.len().min(MAX_DETECTIONS) as u8;
+    for (i, face) in faces.iter().take(MAX_DETECTIONS).enumerate() {
+        let (id, id_confidence) = match face.recognition {
+            Recognition::Identified { id, confidence } => {
+                (u8::from(id), confidence.min(i8::MAX as u8) as i8)
+            }
+            _ => (0, 0),
+        };
+        buffer[5 + i * 8..13 + i * 8].copy_from_slice(&[
+            face.box_confidence,
+            face.box_left,
+            face.box_top,
+            face.box_right,
+            face.box_bottom,
+            id_confidence as u8,
+            id,
+            face.is_facing as u8,
+        ]);
+    }
+    let checksum = crc16::State::<MCRF4XX>::calculate(&buffer[..37]);
+    buffer[37..].copy_from_slice(&checksum.to_le_bytes());
+    buffer
+}
+
+impl<I2C, INT, MODE> PersonSensor<I2C, INT, MODE> {
+    /// Releases the bus and the interrupt pin. The sensor is left in its current mode.
+    pub fn release(self) -> (I2C, INT) {
+        (self.i2c, self.interrupt)
+    }
 }
 
 impl<I2C, INT, MODE> PersonSensor<I2C, INT, MODE>
@@ -70,49 +212,68 @@ where
     I2C: I2c,
 {
     /// Returns the latest results from the sensor.
-    async fn latest_results(
+    pub(crate) async fn latest_results(
         &mut self,
     ) -> Result<heapless::Vec<Face, MAX_DETECTIONS>, ReadError<I2C::Error>> {
+        let buffer = self.read_frame().await?;
+        self.decode(&buffer)
+    }
+
+    /// Decodes a raw frame, dropping the faces rejected by the filter.
+    fn decode(
+        &self,
+        buffer: &[u8; 39],
+    ) -> Result<heapless::Vec<Face, MAX_DETECTIONS>, ReadError<I2C::Error>> {
+        let mut faces = decode_frame(buffer)?;
+        self.filter.apply(&mut faces);
+        Ok(faces)
+    }
+
+    /// Reads the raw frame from the sensor, without validating it.
+    pub(crate) async fn read_frame(&mut self) -> Result<[u8; 39], I2C::Error> {
         let mut buffer = [0u8; 39];
         self.i2c
             .read(PERSON_SENSOR_I2C_ADDRESS, &mut buffer)
             .await?;
+        Ok(buffer)
+    }
 
-        let checksum = crc16::State::<MCRF4XX>::calculate(&buffer[..37]);
-        if u16::from_le_bytes([buffer[37], buffer[38]]) != checksum {
-            return Err(ReadError::<I2C::Error>::ChecksumMismatch);
-        }
+    /// Triggers a single capture and waits for it to complete as configured by [`CaptureWait`].
+    pub(crate) async fn capture<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<heapless::Vec<Face, MAX_DETECTIONS>, ReadError<I2C::Error>> {
+        match self.capture_wait {
+            CaptureWait::Delay { ms } => {
+                self.trigger_capture().await?;
+                delay.delay_ms(ms).await;
+                self.latest_results().await
+            }
+            CaptureWait::UntilChanged {
+                interval_ms,
+                timeout_ms,
+            } => {
+                let previous = self.read_frame().await?;
+                self.trigger_capture().await?;
 
-        let mut faces = heapless::Vec::<Face, MAX_DETECTIONS>::new();
-
-        let num_faces = buffer[4];
-        for face_num in 0..num_faces {
-            let face_start_offset = 5 + face_num as usize * 8;
-
-            let id_confidence = buffer[face_start_offset + 5] as i8;
-            let person_id = match id_confidence {
-                0 => None,
-                _ => Some(PersonID::new_unchecked(buffer[face_start_offset + 6])),
-            };
-
-            let face = Face {
-                box_confidence: buffer[face_start_offset],
-                box_left: buffer[face_start_offset + 1],
-                box_top: buffer[face_start_offset + 2],
-                box_right: buffer[face_start_offset + 3],
-                box_bottom: buffer[face_start_offset + 4],
-                id_confidence,
-                id: person_id,
-                is_facing: buffer[face_start_offset + 7] > 0,
-            };
-
-            match faces.push(face) {
-                Ok(_) => {}
-                Err(_) => break,
-            };
-        }
+                let interval_ms = interval_ms.max(1);
+                let mut waited_ms: u32 = 0;
+                loop {
+                    delay.delay_ms(interval_ms).await;
+                    waited_ms = waited_ms.saturating_add(interval_ms);
 
-        Ok(faces)
+                    let frame = self.read_frame().await?;
+                    let timed_out = waited_ms >= timeout_ms;
+                    if frame != previous || timed_out {
+                        match self.decode(&frame) {
+                            // The frame may have been read while the sensor was updating it
+                            Err(ReadError::ChecksumMismatch) if !timed_out => continue,
+                            result => return result,
+                        }
+                    }
+                }
+            }
+        }
     }
 
     /// Sets the mode of the sensor.
@@ -122,6 +283,25 @@ where
             .await
     }
 
+    /// Requests a single capture. Only meaningful while the sensor is in standby mode.
+    pub(crate) async fn trigger_capture(&mut self) -> Result<(), I2C::Error> {
+        self.i2c
+            .write(PERSON_SENSOR_I2C_ADDRESS, &[0x03, 0x00])
+            .await
+    }
+
+    /// Changes the mode tracked by the type system without communicating with the sensor.
+    pub(crate) fn into_mode<NEW>(self) -> PersonSensor<I2C, INT, NEW> {
+        PersonSensor {
+            i2c: self.i2c,
+            interrupt: self.interrupt,
+            mode: PhantomData,
+            capture_wait: self.capture_wait,
+            revision: self.revision,
+            filter: self.filter,
+        }
+    }
+
     /// Enable / Disable the ID model. With this flag set to false, only bounding boxes are
     /// captured and the framerate is increased.
     pub async fn enable_id_model(&mut self, enable: bool) -> Result<(), I2C::Error> {
@@ -162,21 +342,26 @@ where
             .write(PERSON_SENSOR_I2C_ADDRESS, &[0x07, enabled as u8])
             .await
     }
+
+    /// Replaces the filter applied to every frame read from the sensor.
+    pub fn set_filter(&mut self, filter: DetectionFilter) {
+        self.filter = filter;
+    }
 }
 
 impl<I2C, INT> PersonSensor<I2C, INT, StandbyMode>
 where
     I2C: I2c,
 {
-    /// Capture a single frame and reads the results
-    pub async fn capture_once(
+    /// Capture a single frame and read the results, once the capture has completed.
+    ///
+    /// The capture is requested immediately, and results are read once the sensor has had time to
+    /// run inference, as configured by [`CaptureWait`] on the builder.
+    pub async fn capture_once<D: DelayNs>(
         &mut self,
+        delay: &mut D,
     ) -> Result<heapless::Vec<Face, MAX_DETECTIONS>, ReadError<I2C::Error>> {
-        self.i2c
-            .write(PERSON_SENSOR_I2C_ADDRESS, &[0x03, 0x00])
-            .await?;
-
-        self.latest_results().await
+        self.capture(delay).await
     }
 
     /// Switches the sensor to continuous capture mode
@@ -185,11 +370,7 @@ where
     ) -> Result<PersonSensor<I2C, INT, ContinuousCaptureMode>, I2C::Error> {
         let mut sensor = self;
         sensor.set_mode(PersonSensorMode::Continuous).await?;
-        Ok(PersonSensor {
-            i2c: sensor.i2c,
-            interrupt: sensor.interrupt,
-            mode: PhantomData,
-        })
+        Ok(sensor.into_mode())
     }
 }
 
@@ -204,11 +385,7 @@ where
     ) -> Result<PersonSensor<I2C, INT, StandbyMode>, I2C::Error> {
         let mut sensor = self;
         sensor.set_mode(PersonSensorMode::Standby).await?;
-        Ok(PersonSensor {
-            i2c: sensor.i2c,
-            interrupt: sensor.interrupt,
-            mode: PhantomData,
-        })
+        Ok(sensor.into_mode())
     }
 
     /// Returns the latest results from the sensor. Depending on the device version and
@@ -223,6 +400,35 @@ where
     }
 }
 
+impl<I2C, INT> PersonSensor<I2C, INT, StandbyMode>
+where
+    I2C: I2c,
+    INT: Wait,
+{
+    /// Capture a single frame, using the interrupt pin to detect when a face has been found.
+    ///
+    /// The results are read as soon as the pin rises after the capture is requested. The pin
+    /// stays high while a face is in view, so its level can't tell the new frame from the
+    /// previous one. Without a new rising edge, such as for a frame without faces or when the pin
+    /// is still high from the previous frame, the full `timeout_ms` is waited before the results
+    /// are read. If the pin reports an error, the full timeout is waited instead.
+    pub async fn capture_once_with_interrupt<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        timeout_ms: u32,
+    ) -> Result<heapless::Vec<Face, MAX_DETECTIONS>, ReadError<I2C::Error>> {
+        self.trigger_capture().await?;
+
+        let interrupt = self.interrupt.wait_for_rising_edge();
+        let timeout = delay.delay_ms(timeout_ms);
+        if let Some(Err(_)) = first(interrupt, timeout).await {
+            delay.delay_ms(timeout_ms).await;
+        }
+
+        self.latest_results().await
+    }
+}
+
 impl<I2C, INT> PersonSensor<I2C, INT, ContinuousCaptureMode>
 where
     INT: Wait,
@@ -233,3 +439,20 @@ where
         self.interrupt.wait_for_high().await
     }
 }
+
+/// Runs both futures until one completes. Returns the output of `a`, or `None` if `b` completed
+/// first.
+async fn first<A: Future, B: Future>(a: A, b: B) -> Option<A::Output> {
+    let mut a = pin!(a);
+    let mut b = pin!(b);
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = a.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        if b.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    })
+    .await
+}
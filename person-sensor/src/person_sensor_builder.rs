@@ -3,18 +3,23 @@ use core::marker::PhantomData;
 use embedded_hal_async::{digital::Wait, i2c::I2c};
 
 use crate::{
-    person_sensor::{ContinuousCaptureMode, PersonSensorMode, StandbyMode},
-    PersonSensor,
+    filter::DetectionFilter,
+    person_sensor::{CaptureWait, ContinuousCaptureMode, PersonSensorMode, StandbyMode},
+    probe, PersonSensor, ProbeError, SensorRevision,
 };
 
 /// Builder for the [`PersonSensor`] driver
 ///
-/// Use this to create a new instance of the `PersonSensor` driver
+/// Use this to create a new instance of the `PersonSensor` driver. The bus can be owned, borrowed
+/// as `&mut I2C`, or a device handle on a bus shared with other devices.
 pub struct PersonSensorBuilder<I2C, INT, MODE> {
     i2c: I2C,
     interrupt: INT,
     mode: PhantomData<MODE>,
     id_enabled: bool,
+    capture_wait: Option<CaptureWait>,
+    revision: SensorRevision,
+    filter: DetectionFilter,
 }
 
 impl<I2C> PersonSensorBuilder<I2C, (), ()>
@@ -28,6 +33,9 @@ where
             interrupt: (),
             mode: PhantomData,
             id_enabled,
+            capture_wait: None,
+            revision: SensorRevision::Unknown,
+            filter: DetectionFilter::new(),
         }
     }
 
@@ -41,6 +49,9 @@ where
             interrupt: (),
             mode: PhantomData,
             id_enabled,
+            capture_wait: None,
+            revision: SensorRevision::Unknown,
+            filter: DetectionFilter::new(),
         }
     }
 }
@@ -56,20 +67,56 @@ where
             interrupt,
             mode: self.mode,
             id_enabled: self.id_enabled,
+            capture_wait: self.capture_wait,
+            revision: self.revision,
+            filter: self.filter,
         }
     }
 }
 
+impl<I2C, INT, MODE> PersonSensorBuilder<I2C, INT, MODE>
+where
+    I2C: I2c,
+{
+    /// Sets how single-shot captures wait for the sensor to finish. Defaults to a fixed delay of
+    /// one frame for the configured revision.
+    pub fn with_capture_wait(self, capture_wait: CaptureWait) -> Self {
+        Self {
+            capture_wait: Some(capture_wait),
+            ..self
+        }
+    }
+
+    /// Sets the board revision of the sensor, if known. Defaults to [`SensorRevision::Unknown`].
+    pub fn with_revision(self, revision: SensorRevision) -> Self {
+        Self { revision, ..self }
+    }
+
+    /// Sets the filter applied to every frame read from the sensor. Defaults to keeping every
+    /// face.
+    pub fn with_filter(self, filter: DetectionFilter) -> Self {
+        Self { filter, ..self }
+    }
+}
+
 impl<I2C, INT> PersonSensorBuilder<I2C, INT, ContinuousCaptureMode>
 where
     I2C: I2c,
 {
-    /// Initialize the sensor in continuous mode
-    pub async fn build(self) -> Result<PersonSensor<I2C, INT, ContinuousCaptureMode>, I2C::Error> {
+    /// Check that the sensor is present, then initialize it in continuous mode
+    pub async fn build(
+        mut self,
+    ) -> Result<PersonSensor<I2C, INT, ContinuousCaptureMode>, ProbeError<I2C::Error>> {
+        probe(&mut self.i2c).await?;
         let mut sensor = PersonSensor {
             i2c: self.i2c,
             interrupt: self.interrupt,
             mode: PhantomData,
+            capture_wait: self
+                .capture_wait
+                .unwrap_or_else(|| self.revision.capture_wait()),
+            revision: self.revision,
+            filter: self.filter,
         };
         sensor.set_mode(PersonSensorMode::Continuous).await?;
         sensor.enable_id_model(self.id_enabled).await?;
@@ -81,12 +128,20 @@ impl<I2C, INT> PersonSensorBuilder<I2C, INT, StandbyMode>
 where
     I2C: I2c,
 {
-    /// Initialize the sensor in standby mode
-    pub async fn build(self) -> Result<PersonSensor<I2C, INT, StandbyMode>, I2C::Error> {
+    /// Check that the sensor is present, then initialize it in standby mode
+    pub async fn build(
+        mut self,
+    ) -> Result<PersonSensor<I2C, INT, StandbyMode>, ProbeError<I2C::Error>> {
+        probe(&mut self.i2c).await?;
         let mut sensor = PersonSensor {
             i2c: self.i2c,
             interrupt: self.interrupt,
             mode: PhantomData,
+            capture_wait: self
+                .capture_wait
+                .unwrap_or_else(|| self.revision.capture_wait()),
+            revision: self.revision,
+            filter: self.filter,
         };
         sensor.set_mode(PersonSensorMode::Standby).await?;
         sensor.enable_id_model(true).await?;
@@ -0,0 +1,226 @@
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+
+use crate::{
+    person_sensor::{ContinuousCaptureMode, PersonSensorMode, StandbyMode},
+    Clock, Detections, PersonSensor, ReadError,
+};
+
+/// Timing configuration for the [`PowerManager`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PowerConfig {
+    /// How often a single frame is captured while the sensor is in standby.
+    pub standby_interval_ms: u32,
+    /// How often detections are read while the sensor is capturing continuously.
+    pub continuous_interval_ms: u32,
+    /// How long the sensor stays in continuous mode after the last face was seen.
+    pub idle_timeout_ms: u32,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            standby_interval_ms: 5_000,
+            continuous_interval_ms: 200,
+            idle_timeout_ms: 30_000,
+        }
+    }
+}
+
+/// The mode the [`PowerManager`] currently keeps the sensor in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PowerState {
+    /// Low power: a single frame is captured every `standby_interval_ms`.
+    Standby,
+    /// Somebody was seen recently, the sensor is capturing continuously.
+    Continuous,
+}
+
+/// An error returned by [`PowerManager::poll`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PollError<E> {
+    /// The detections couldn't be read.
+    Read(ReadError<E>),
+    /// The detections were read, but switching the sensor to the next mode failed. The manager
+    /// stays in its current state, and the switch is retried on the next poll if still needed.
+    Transition { faces: Detections, error: E },
+}
+
+impl<E> From<ReadError<E>> for PollError<E> {
+    fn from(error: ReadError<E>) -> Self {
+        Self::Read(error)
+    }
+}
+
+/// A sensor handed back by [`PowerManager::release`] or
+/// [`CommandExecutor::release`](crate::command::CommandExecutor::release), in whichever mode it
+/// was left in.
+#[derive(Debug)]
+pub enum ManagedSensor<I2C, INT> {
+    Standby(PersonSensor<I2C, INT, StandbyMode>),
+    Continuous(PersonSensor<I2C, INT, ContinuousCaptureMode>),
+}
+
+/// Duty-cycles the sensor between standby and continuous capture.
+///
+/// While nobody is around the sensor is left in standby, and a single frame is captured every
+/// `standby_interval_ms`. As soon as a face is detected the sensor is switched to continuous
+/// mode and read every `continuous_interval_ms`. Once no face has been seen for
+/// `idle_timeout_ms`, the sensor is put back into standby.
+///
+/// The manager never sleeps on its own when using [`poll`](Self::poll). Call it at (or after)
+/// [`next_poll_ms`](Self::next_poll_ms), or let [`wait_and_poll`](Self::wait_and_poll) do the
+/// waiting.
+///
+/// Example:
+/// ```ignore
+/// let sensor = PersonSensorBuilder::new_standby(i2c, false).build().await.unwrap();
+/// let clock = || Instant::now().as_millis();
+/// let mut manager = PowerManager::new(sensor, clock, PowerConfig::default());
+///
+/// loop {
+///     let faces = manager.wait_and_poll(&mut Delay).await.unwrap();
+///     // Do something with the results
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PowerManager<I2C, INT, CLOCK> {
+    sensor: PersonSensor<I2C, INT, ()>,
+    clock: CLOCK,
+    config: PowerConfig,
+    state: PowerState,
+    last_detection_ms: u64,
+    next_poll_ms: u64,
+}
+
+impl<I2C, INT, CLOCK> PowerManager<I2C, INT, CLOCK>
+where
+    I2C: I2c,
+    CLOCK: Clock,
+{
+    /// Create a power manager from a sensor in standby mode. The first poll captures immediately.
+    pub fn new(
+        sensor: PersonSensor<I2C, INT, StandbyMode>,
+        clock: CLOCK,
+        config: PowerConfig,
+    ) -> Self {
+        let now = clock.now_ms();
+        Self {
+            sensor: sensor.into_mode(),
+            clock,
+            config,
+            state: PowerState::Standby,
+            last_detection_ms: now,
+            next_poll_ms: now,
+        }
+    }
+
+    /// Create a power manager from a sensor in continuous mode. The sensor will remain in
+    /// continuous mode for at least `idle_timeout_ms`.
+    pub fn from_continuous(
+        sensor: PersonSensor<I2C, INT, ContinuousCaptureMode>,
+        clock: CLOCK,
+        config: PowerConfig,
+    ) -> Self {
+        let now = clock.now_ms();
+        Self {
+            sensor: sensor.into_mode(),
+            clock,
+            config,
+            state: PowerState::Continuous,
+            last_detection_ms: now,
+            next_poll_ms: now,
+        }
+    }
+
+    /// The mode the sensor is currently in.
+    pub fn state(&self) -> PowerState {
+        self.state
+    }
+
+    /// The time at which the next poll is due, as reported by the clock.
+    pub fn next_poll_ms(&self) -> u64 {
+        self.next_poll_ms
+    }
+
+    /// Read detections according to the current state, then switch modes if needed.
+    ///
+    /// In standby a single frame is captured, waiting for it as configured by
+    /// [`CaptureWait`](crate::CaptureWait). In continuous mode the latest detections are read.
+    /// A mode transition that fails leaves the state unchanged, so the transition is retried on
+    /// the next poll. The detections read before it are returned with the error, and the next
+    /// poll is scheduled as usual.
+    pub async fn poll<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<Detections, PollError<I2C::Error>> {
+        let faces = match self.state {
+            PowerState::Standby => self.sensor.capture(delay).await?,
+            PowerState::Continuous => self.sensor.latest_results().await?,
+        };
+
+        let now = self.clock.now_ms();
+        if !faces.is_empty() {
+            self.last_detection_ms = now;
+        }
+
+        let next_state = match self.state {
+            PowerState::Standby if !faces.is_empty() => PowerState::Continuous,
+            PowerState::Continuous
+                if now.saturating_sub(self.last_detection_ms)
+                    >= u64::from(self.config.idle_timeout_ms) =>
+            {
+                PowerState::Standby
+            }
+            state => state,
+        };
+
+        let transition = if next_state == self.state {
+            Ok(())
+        } else {
+            let mode = match next_state {
+                PowerState::Standby => PersonSensorMode::Standby,
+                PowerState::Continuous => PersonSensorMode::Continuous,
+            };
+            self.sensor.set_mode(mode).await
+        };
+        if transition.is_ok() {
+            self.state = next_state;
+        }
+
+        let interval = match self.state {
+            PowerState::Standby => self.config.standby_interval_ms,
+            PowerState::Continuous => self.config.continuous_interval_ms,
+        };
+        self.next_poll_ms = now.saturating_add(u64::from(interval));
+
+        match transition {
+            Ok(()) => Ok(faces),
+            Err(error) => Err(PollError::Transition { faces, error }),
+        }
+    }
+
+    /// Wait until the next poll is due, then [`poll`](Self::poll).
+    pub async fn wait_and_poll<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<Detections, PollError<I2C::Error>> {
+        let remaining = self.next_poll_ms.saturating_sub(self.clock.now_ms());
+        if remaining > 0 {
+            delay
+                .delay_ms(u32::try_from(remaining).unwrap_or(u32::MAX))
+                .await;
+        }
+        self.poll(delay).await
+    }
+
+    /// Stop managing the sensor and return it in its current mode.
+    pub fn release(self) -> ManagedSensor<I2C, INT> {
+        match self.state {
+            PowerState::Standby => ManagedSensor::Standby(self.sensor.into_mode()),
+            PowerState::Continuous => ManagedSensor::Continuous(self.sensor.into_mode()),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use embedded_hal_async::i2c::{Error, ErrorKind, I2c, NoAcknowledgeSource};
+
+use crate::person_sensor::{decode_frame, PERSON_SENSOR_I2C_ADDRESS};
+use crate::ReadError;
+
+/// The frame size reported in the header of every frame, as a little-endian u16.
+const FRAME_DATA_SIZE: [u8; 2] = [0x21, 0x00];
+
+/// The reasons a sensor can fail to be detected or initialized.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ProbeError<E> {
+    /// No device acknowledged the sensor's address. Check the wiring and the power supply.
+    NotDetected,
+    /// A device responded, but not with a well-formed frame. Another device may be using the
+    /// address, or the bus may be noisy.
+    InvalidResponse,
+    /// The bus failed for another reason.
+    I2CError(E),
+}
+
+impl<E: Error> From<E> for ProbeError<E> {
+    fn from(error: E) -> Self {
+        match error.kind() {
+            ErrorKind::NoAcknowledge(
+                NoAcknowledgeSource::Address | NoAcknowledgeSource::Unknown,
+            ) => Self::NotDetected,
+            _ => Self::I2CError(error),
+        }
+    }
+}
+
+/// Checks that a person sensor is present on the bus, without configuring it.
+///
+/// A single frame is read from the sensor's address, which must be acknowledged and have a valid
+/// header and checksum. This is done by the builder before configuring the sensor, and is
+/// exposed to diagnose wiring problems.
+///
+/// Example:
+/// ```ignore
+/// match person_sensor::probe(&mut i2c).await {
+///     Ok(()) => info!("Person sensor found"),
+///     Err(ProbeError::NotDetected) => warn!("No person sensor, check the wiring"),
+///     Err(error) => warn!("Person sensor not responding correctly: {:?}", error),
+/// }
+/// ```
+pub async fn probe<I2C: I2c>(i2c: &mut I2C) -> Result<(), ProbeError<I2C::Error>> {
+    let mut buffer = [0u8; 39];
+    i2c.read(PERSON_SENSOR_I2C_ADDRESS, &mut buffer).await?;
+
+    if buffer[2..4] != FRAME_DATA_SIZE {
+        return Err(ProbeError::InvalidResponse);
+    }
+    match decode_frame::<I2C::Error>(&buffer) {
+        Ok(_) => Ok(()),
+        Err(ReadError::ChecksumMismatch) => Err(ProbeError::InvalidResponse),
+        Err(ReadError::I2CError(error)) => Err(ProbeError::I2CError(error)),
+    }
+}
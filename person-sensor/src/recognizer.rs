@@ -0,0 +1,131 @@
+use crate::{Face, PersonID, Recognition};
+
+/// Thresholds used by the [`Recognizer`].
+///
+/// Scores are the ID confidence of a person averaged over the whole window, with frames where
+/// they weren't recognized counting as 0. Keeping `reject_score` below `accept_score` prevents
+/// flickering between recognized and unknown when the score hovers around a single threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RecognizerConfig {
+    /// The score at which a person becomes recognized.
+    pub accept_score: u8,
+    /// The score below which a recognized person is lost.
+    pub reject_score: u8,
+}
+
+impl Default for RecognizerConfig {
+    fn default() -> Self {
+        Self {
+            accept_score: 70,
+            reject_score: 40,
+        }
+    }
+}
+
+/// Changes in the recognized person reported by the [`Recognizer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RecognitionEvent {
+    Recognized(PersonID),
+    Lost(PersonID),
+}
+
+/// Smooths recognition results over the last `WINDOW` frames.
+///
+/// Each frame contributes the identified face with the highest ID confidence, if any. At most one
+/// person is recognized at a time. When somebody else appears, the current person stays
+/// recognized until their score drops below `reject_score`, which can take up to `WINDOW` frames
+/// as their frames leave the window. Only then is the current person lost, and the new person can
+/// be recognized from the following frame once their own score reaches `accept_score`.
+///
+/// Example:
+/// ```ignore
+/// let mut recognizer = Recognizer::<8>::new(RecognizerConfig::default());
+///
+/// loop {
+///     let faces = person_sensor.get_detections().await?;
+///     match recognizer.update(&faces) {
+///         Some(RecognitionEvent::Recognized(id)) => door.unlock(id),
+///         Some(RecognitionEvent::Lost(_)) => door.lock(),
+///         None => {}
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Recognizer<const WINDOW: usize> {
+    config: RecognizerConfig,
+    window: heapless::Deque<Option<(PersonID, u8)>, WINDOW>,
+    current: Option<PersonID>,
+}
+
+impl<const WINDOW: usize> Recognizer<WINDOW> {
+    pub fn new(config: RecognizerConfig) -> Self {
+        Self {
+            config,
+            window: heapless::Deque::new(),
+            current: None,
+        }
+    }
+
+    /// The person currently recognized.
+    pub fn current(&self) -> Option<PersonID> {
+        self.current
+    }
+
+    /// The averaged ID confidence of `id` over the window, from 0 to 100.
+    pub fn score(&self, id: PersonID) -> u8 {
+        let total: u32 = self
+            .window
+            .iter()
+            .filter_map(|observation| match observation {
+                Some((observed, confidence)) if *observed == id => Some(u32::from(*confidence)),
+                _ => None,
+            })
+            .sum();
+        (total / WINDOW.max(1) as u32) as u8
+    }
+
+    /// Add the faces from the next frame, returning a change in the recognized person if any.
+    pub fn update(&mut self, faces: &[Face]) -> Option<RecognitionEvent> {
+        let observation = faces
+            .iter()
+            .filter_map(|face| match face.recognition {
+                Recognition::Identified { id, confidence } => Some((id, confidence)),
+                _ => None,
+            })
+            .max_by_key(|(_, confidence)| *confidence);
+
+        if self.window.is_full() {
+            self.window.pop_front();
+        }
+        // Only fails if the window has no capacity at all
+        _ = self.window.push_back(observation);
+
+        if let Some(current) = self.current {
+            if self.score(current) < self.config.reject_score {
+                self.current = None;
+                return Some(RecognitionEvent::Lost(current));
+            }
+            return None;
+        }
+
+        let (best, score) = self
+            .window
+            .iter()
+            .flatten()
+            .map(|(id, _)| (*id, self.score(*id)))
+            .max_by_key(|(_, score)| *score)?;
+        if score >= self.config.accept_score {
+            self.current = Some(best);
+            return Some(RecognitionEvent::Recognized(best));
+        }
+        None
+    }
+
+    /// Forget all frames and the current person, without reporting them as lost.
+    pub fn reset(&mut self) {
+        self.window.clear();
+        self.current = None;
+    }
+}
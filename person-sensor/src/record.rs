@@ -0,0 +1,401 @@
+//! Recording sensor sessions, and replaying them into the driver.
+//!
+//! A [`Recorder`] wraps the I2C bus used by the driver and logs every transaction with a
+//! timestamp, including the raw frames read from the sensor. A [`Replay`] feeds a recording back
+//! into the driver deterministically, e.g. to reproduce a bug reported from the field or to run
+//! trackers offline.
+//!
+//! A recording starts with the magic bytes `PSR` and [`RECORDING_VERSION`], followed by one
+//! record per operation:
+//!
+//! | Field        | Size | Description                                             |
+//! |--------------|------|---------------------------------------------------------|
+//! | kind         | 1    | 0 for a write, 1 for a read, 2 for a failed transaction |
+//! | address      | 1    | The 7-bit address of the transaction                    |
+//! | timestamp_ms | 4    | Little endian, since the first record                   |
+//! | len          | 2    | Little endian length of the data                        |
+//! | data         | len  | The bytes written or read, or the error kind            |
+//!
+//! A failed transaction is recorded as a single record, as it is unknown which of its operations
+//! took effect.
+//!
+//! Example:
+//! ```ignore
+//! let mut recording = heapless::Vec::<u8, 4096>::new();
+//! let i2c = Recorder::new(i2c, clock, |bytes: &[u8]| {
+//!     _ = recording.extend_from_slice(bytes);
+//! });
+//! let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, true).build().await?;
+//!
+//! // Later, e.g. in a regression test
+//! let replay = Replay::new(&recording)?;
+//! let mut person_sensor = PersonSensorBuilder::new_continuous(replay.bus(), true).build().await?;
+//! let faces = person_sensor.get_detections().await?;
+//! ```
+
+use core::cell::{Cell, RefCell};
+
+use embedded_hal_async::i2c::{
+    self, ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation, SevenBitAddress,
+};
+
+use crate::Clock;
+
+/// The version of the format produced by the [`Recorder`].
+pub const RECORDING_VERSION: u8 = 1;
+
+const MAGIC: [u8; 3] = *b"PSR";
+const HEADER_LEN: usize = 8;
+
+const WRITE: u8 = 0;
+const READ: u8 = 1;
+const ERROR: u8 = 2;
+
+/// Receives the bytes of a recording as it is written.
+///
+/// Any `FnMut(&[u8])` closure is a sink, so a recording can be appended to a buffer, or streamed
+/// to flash or a serial port.
+pub trait RecordSink {
+    fn write(&mut self, bytes: &[u8]);
+}
+
+impl<F> RecordSink for F
+where
+    F: FnMut(&[u8]),
+{
+    fn write(&mut self, bytes: &[u8]) {
+        self(bytes)
+    }
+}
+
+/// Wraps an I2C bus, recording every transaction to a [`RecordSink`].
+///
+/// Timestamps are recorded in milliseconds since the first transaction, and wrap after about 49
+/// days. Operations longer than `u16::MAX` bytes are recorded truncated.
+#[derive(Debug)]
+pub struct Recorder<I2C, CLOCK, SINK> {
+    i2c: I2C,
+    clock: CLOCK,
+    sink: SINK,
+    start_ms: Option<u64>,
+}
+
+impl<I2C, CLOCK, SINK> Recorder<I2C, CLOCK, SINK>
+where
+    I2C: I2c,
+    CLOCK: Clock,
+    SINK: RecordSink,
+{
+    pub fn new(i2c: I2C, clock: CLOCK, sink: SINK) -> Self {
+        Self {
+            i2c,
+            clock,
+            sink,
+            start_ms: None,
+        }
+    }
+
+    /// Stop recording, returning the bus and the sink.
+    pub fn release(self) -> (I2C, SINK) {
+        (self.i2c, self.sink)
+    }
+
+    fn record(&mut self, kind: u8, address: u8, timestamp_ms: u32, data: &[u8]) {
+        let len = data.len().min(usize::from(u16::MAX));
+        let mut header = [0u8; HEADER_LEN];
+        header[0] = kind;
+        header[1] = address;
+        header[2..6].copy_from_slice(&timestamp_ms.to_le_bytes());
+        header[6..8].copy_from_slice(&(len as u16).to_le_bytes());
+        self.sink.write(&header);
+        self.sink.write(&data[..len]);
+    }
+}
+
+impl<I2C, CLOCK, SINK> ErrorType for Recorder<I2C, CLOCK, SINK>
+where
+    I2C: I2c,
+{
+    type Error = I2C::Error;
+}
+
+impl<I2C, CLOCK, SINK> I2c<SevenBitAddress> for Recorder<I2C, CLOCK, SINK>
+where
+    I2C: I2c,
+    CLOCK: Clock,
+    SINK: RecordSink,
+{
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let result = self.i2c.transaction(address, operations).await;
+
+        let now = self.clock.now_ms();
+        let start_ms = match self.start_ms {
+            Some(start_ms) => start_ms,
+            None => {
+                self.sink.write(&MAGIC);
+                self.sink.write(&[RECORDING_VERSION]);
+                *self.start_ms.insert(now)
+            }
+        };
+        let timestamp_ms = now.saturating_sub(start_ms) as u32;
+
+        match &result {
+            Ok(()) => {
+                for operation in operations.iter() {
+                    match operation {
+                        Operation::Write(data) => self.record(WRITE, address, timestamp_ms, data),
+                        Operation::Read(data) => self.record(READ, address, timestamp_ms, data),
+                    }
+                }
+            }
+            Err(error) => {
+                let kind = encode_error_kind(i2c::Error::kind(error));
+                self.record(ERROR, address, timestamp_ms, &[kind]);
+            }
+        }
+        result
+    }
+}
+
+/// A single entry of a recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Record<'a> {
+    Write {
+        address: u8,
+        timestamp_ms: u32,
+        data: &'a [u8],
+    },
+    Read {
+        address: u8,
+        timestamp_ms: u32,
+        data: &'a [u8],
+    },
+    /// A transaction that failed with the given error.
+    Error {
+        address: u8,
+        timestamp_ms: u32,
+        kind: ErrorKind,
+    },
+}
+
+impl Record<'_> {
+    pub fn address(&self) -> u8 {
+        match self {
+            Self::Write { address, .. }
+            | Self::Read { address, .. }
+            | Self::Error { address, .. } => *address,
+        }
+    }
+
+    /// Milliseconds since the start of the recording.
+    pub fn timestamp_ms(&self) -> u32 {
+        match self {
+            Self::Write { timestamp_ms, .. }
+            | Self::Read { timestamp_ms, .. }
+            | Self::Error { timestamp_ms, .. } => *timestamp_ms,
+        }
+    }
+}
+
+/// Errors encountered while reading or replaying a recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReplayError {
+    /// The recording is truncated, malformed, or was written by an unsupported version.
+    InvalidRecording,
+    /// The driver performed an operation after the end of the recording.
+    Finished,
+    /// The driver performed a different operation than the one recorded.
+    Diverged,
+    /// The recorded transaction failed with this error.
+    Recorded(ErrorKind),
+}
+
+impl i2c::Error for ReplayError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Recorded(kind) => *kind,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// Iterates over the records of a recording, stopping at the first malformed record.
+#[derive(Debug, Clone)]
+pub struct Records<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Records<'a> {
+    /// Validates the header of `recording`. A recording without any transactions is empty.
+    pub fn new(recording: &'a [u8]) -> Result<Self, ReplayError> {
+        match recording {
+            [] => Ok(Self { bytes: &[] }),
+            [m0, m1, m2, RECORDING_VERSION, bytes @ ..] if [*m0, *m1, *m2] == MAGIC => {
+                Ok(Self { bytes })
+            }
+            _ => Err(ReplayError::InvalidRecording),
+        }
+    }
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<Record<'a>, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        let Some((header, rest)) = self.bytes.split_first_chunk::<HEADER_LEN>() else {
+            self.bytes = &[];
+            return Some(Err(ReplayError::InvalidRecording));
+        };
+        let len = usize::from(u16::from_le_bytes([header[6], header[7]]));
+        if rest.len() < len {
+            self.bytes = &[];
+            return Some(Err(ReplayError::InvalidRecording));
+        }
+        let (data, rest) = rest.split_at(len);
+        self.bytes = rest;
+
+        let address = header[1];
+        let timestamp_ms = u32::from_le_bytes([header[2], header[3], header[4], header[5]]);
+        let record = match (header[0], data) {
+            (WRITE, data) => Record::Write {
+                address,
+                timestamp_ms,
+                data,
+            },
+            (READ, data) => Record::Read {
+                address,
+                timestamp_ms,
+                data,
+            },
+            (ERROR, [kind]) => Record::Error {
+                address,
+                timestamp_ms,
+                kind: decode_error_kind(*kind),
+            },
+            _ => {
+                self.bytes = &[];
+                return Some(Err(ReplayError::InvalidRecording));
+            }
+        };
+        Some(Ok(record))
+    }
+}
+
+/// Replays a recording into the driver.
+///
+/// The driver talks to the replay through the bus returned by [`bus`](Self::bus). Each operation
+/// must match the next recorded one: writes must write the same bytes to the same address, and
+/// reads return the recorded data. Recorded failures are returned as
+/// [`ReplayError::Recorded`].
+#[derive(Debug)]
+pub struct Replay<'a> {
+    records: RefCell<Records<'a>>,
+    now_ms: Cell<u32>,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(recording: &'a [u8]) -> Result<Self, ReplayError> {
+        Ok(Self {
+            records: RefCell::new(Records::new(recording)?),
+            now_ms: Cell::new(0),
+        })
+    }
+
+    /// The bus to give to the driver.
+    pub fn bus(&self) -> ReplayBus<'_, 'a> {
+        ReplayBus { replay: self }
+    }
+
+    /// The timestamp of the last replayed record. Use it as the [`Clock`] for anything driven by
+    /// the replay, so it sees the same timing as the recorded session.
+    pub fn now_ms(&self) -> u64 {
+        u64::from(self.now_ms.get())
+    }
+
+    /// Whether every record has been replayed.
+    pub fn is_finished(&self) -> bool {
+        self.records.borrow().bytes.is_empty()
+    }
+
+    fn next_record(&self) -> Result<Record<'a>, ReplayError> {
+        let record = self
+            .records
+            .borrow_mut()
+            .next()
+            .ok_or(ReplayError::Finished)??;
+        self.now_ms.set(record.timestamp_ms());
+        Ok(record)
+    }
+}
+
+/// The I2C bus of a [`Replay`].
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayBus<'r, 'a> {
+    replay: &'r Replay<'a>,
+}
+
+impl ErrorType for ReplayBus<'_, '_> {
+    type Error = ReplayError;
+}
+
+impl I2c<SevenBitAddress> for ReplayBus<'_, '_> {
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        for (i, operation) in operations.iter_mut().enumerate() {
+            let record = self.replay.next_record()?;
+            if record.address() != address {
+                return Err(ReplayError::Diverged);
+            }
+            match (operation, record) {
+                // Failed transactions are recorded once, in place of their first operation
+                (_, Record::Error { kind, .. }) if i == 0 => {
+                    return Err(ReplayError::Recorded(kind));
+                }
+                (Operation::Write(written), Record::Write { data, .. }) if *written == data => {}
+                (Operation::Read(buffer), Record::Read { data, .. })
+                    if buffer.len() == data.len() =>
+                {
+                    buffer.copy_from_slice(data);
+                }
+                _ => return Err(ReplayError::Diverged),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn encode_error_kind(kind: ErrorKind) -> u8 {
+    match kind {
+        ErrorKind::Bus => 0,
+        ErrorKind::ArbitrationLoss => 1,
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address) => 2,
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data) => 3,
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Unknown) => 4,
+        ErrorKind::Overrun => 5,
+        _ => u8::MAX,
+    }
+}
+
+fn decode_error_kind(kind: u8) -> ErrorKind {
+    match kind {
+        0 => ErrorKind::Bus,
+        1 => ErrorKind::ArbitrationLoss,
+        2 => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+        3 => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+        4 => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Unknown),
+        5 => ErrorKind::Overrun,
+        _ => ErrorKind::Other,
+    }
+}
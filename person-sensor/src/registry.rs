@@ -0,0 +1,256 @@
+use embedded_hal_async::i2c::I2c;
+
+use crate::{PersonID, PersonSensor};
+
+/// The number of identities the sensor can be calibrated on.
+pub const MAX_IDS: usize = 8;
+
+const FORMAT_VERSION: u8 = 1;
+const HEADER_LEN: usize = 3;
+
+/// Errors returned by the [`IdentityRegistry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RegistryError {
+    /// All IDs are in use.
+    Full,
+    /// The ID is outside the range the sensor supports.
+    InvalidId,
+    /// The label is longer than the registry's label capacity.
+    LabelTooLong,
+    /// The buffer is too small to hold the serialized registry.
+    BufferTooSmall,
+    /// The serialized data is malformed or was written by an unsupported version.
+    InvalidData,
+}
+
+/// Tracks which [`PersonID`] slots are in use, and who they belong to.
+///
+/// The sensor only stores calibrations by ID, so the registry keeps the application's label for
+/// each ID alongside whether IDs are persisted. Labels can be up to `N` bytes long.
+///
+/// To keep the registry consistent with the sensor, erase IDs and change persistence through
+/// [`erase`](Self::erase) and [`set_persist`](Self::set_persist) rather than calling the sensor
+/// directly, and store the registry whenever it changes if IDs are persisted.
+///
+/// Example:
+/// ```ignore
+/// let mut registry = IdentityRegistry::<16>::new();
+///
+/// let id = registry.allocate("alice")?;
+/// if person_sensor.calibrate(id, &config, &mut delay).await.is_err() {
+///     registry.release(id);
+/// }
+///
+/// let len = registry.to_bytes(&mut buffer)?;
+/// flash.write(REGISTRY_OFFSET, &buffer[..len])?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IdentityRegistry<const N: usize> {
+    labels: [Option<heapless::String<N>>; MAX_IDS],
+    persist: bool,
+}
+
+impl<const N: usize> Default for IdentityRegistry<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> IdentityRegistry<N> {
+    /// Create an empty registry that does not persist IDs.
+    pub const fn new() -> Self {
+        Self {
+            labels: [const { None }; MAX_IDS],
+            persist: false,
+        }
+    }
+
+    /// The label assigned to `id`, if it is in use.
+    pub fn get(&self, id: PersonID) -> Option<&str> {
+        self.labels.get(usize::from(u8::from(id)))?.as_deref()
+    }
+
+    /// The ID assigned to `label`, if any.
+    pub fn find(&self, label: &str) -> Option<PersonID> {
+        self.iter()
+            .find(|(_, existing)| *existing == label)
+            .map(|(id, _)| id)
+    }
+
+    /// Whether `id` is available for enrollment.
+    pub fn is_free(&self, id: PersonID) -> bool {
+        matches!(self.labels.get(usize::from(u8::from(id))), Some(None))
+    }
+
+    /// The lowest ID available for enrollment.
+    pub fn next_free(&self) -> Option<PersonID> {
+        self.free_ids().next()
+    }
+
+    /// All IDs available for enrollment, in ascending order.
+    pub fn free_ids(&self) -> impl Iterator<Item = PersonID> + '_ {
+        all_ids().filter(|id| self.is_free(*id))
+    }
+
+    /// All IDs in use and their labels, in ascending order of ID.
+    pub fn iter(&self) -> impl Iterator<Item = (PersonID, &str)> + '_ {
+        all_ids().filter_map(|id| self.get(id).map(|label| (id, label)))
+    }
+
+    /// The number of IDs in use.
+    pub fn len(&self) -> usize {
+        self.labels.iter().filter(|label| label.is_some()).count()
+    }
+
+    /// Whether no IDs are in use.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the sensor was configured to persist IDs through this registry.
+    pub fn persist(&self) -> bool {
+        self.persist
+    }
+
+    /// Reserve the next free ID for `label`. The ID should then be calibrated on the sensor, and
+    /// released again if calibration fails.
+    pub fn allocate(&mut self, label: &str) -> Result<PersonID, RegistryError> {
+        let id = self.next_free().ok_or(RegistryError::Full)?;
+        self.assign(id, label)?;
+        Ok(id)
+    }
+
+    /// Assign `label` to `id`, returning the label it replaces.
+    ///
+    /// Fails with [`RegistryError::InvalidId`] if `id` is outside the sensor's range, which can
+    /// only happen for IDs created with [`PersonID::new_unchecked`].
+    pub fn assign(
+        &mut self,
+        id: PersonID,
+        label: &str,
+    ) -> Result<Option<heapless::String<N>>, RegistryError> {
+        // Lengths are serialized as a single byte
+        if label.len() > usize::from(u8::MAX) {
+            return Err(RegistryError::LabelTooLong);
+        }
+        let slot = self
+            .labels
+            .get_mut(usize::from(u8::from(id)))
+            .ok_or(RegistryError::InvalidId)?;
+        let label = heapless::String::try_from(label).map_err(|_| RegistryError::LabelTooLong)?;
+        Ok(slot.replace(label))
+    }
+
+    /// Free `id`, returning its label.
+    ///
+    /// > Note: the sensor can only erase all IDs at once. The calibration for this ID remains on
+    /// > the sensor until the ID is calibrated again, or all IDs are erased.
+    pub fn release(&mut self, id: PersonID) -> Option<heapless::String<N>> {
+        self.labels.get_mut(usize::from(u8::from(id)))?.take()
+    }
+
+    /// Wipe all IDs from the sensor, then from the registry.
+    pub async fn erase<I2C, INT, MODE>(
+        &mut self,
+        sensor: &mut PersonSensor<I2C, INT, MODE>,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: I2c,
+    {
+        sensor.erase_ids().await?;
+        self.labels = [const { None }; MAX_IDS];
+        Ok(())
+    }
+
+    /// Configure whether the sensor persists IDs when unpowered, and record it in the registry.
+    pub async fn set_persist<I2C, INT, MODE>(
+        &mut self,
+        sensor: &mut PersonSensor<I2C, INT, MODE>,
+        persist: bool,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: I2c,
+    {
+        sensor.set_persist_ids(persist).await?;
+        self.persist = persist;
+        Ok(())
+    }
+
+    /// Apply the registry's persistence setting to the sensor, e.g. after loading the registry.
+    pub async fn sync<I2C, INT, MODE>(
+        &self,
+        sensor: &mut PersonSensor<I2C, INT, MODE>,
+    ) -> Result<(), I2C::Error>
+    where
+        I2C: I2c,
+    {
+        sensor.set_persist_ids(self.persist).await
+    }
+
+    /// The number of bytes needed by [`to_bytes`](Self::to_bytes).
+    pub fn serialized_len(&self) -> usize {
+        HEADER_LEN + self.iter().map(|(_, label)| 1 + label.len()).sum::<usize>()
+    }
+
+    /// Serialize the registry into `buffer`, returning the number of bytes written.
+    ///
+    /// The format is a version byte, a flags byte, a bitmask of the IDs in use, then a length
+    /// prefixed label for each ID in use in ascending order.
+    pub fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize, RegistryError> {
+        let len = self.serialized_len();
+        if buffer.len() < len {
+            return Err(RegistryError::BufferTooSmall);
+        }
+
+        let used = self
+            .iter()
+            .filter_map(|(id, _)| 1u8.checked_shl(u32::from(u8::from(id))))
+            .fold(0, |mask, bit| mask | bit);
+        buffer[..HEADER_LEN].copy_from_slice(&[FORMAT_VERSION, self.persist as u8, used]);
+
+        let mut offset = HEADER_LEN;
+        for (_, label) in self.iter() {
+            buffer[offset] = label.len() as u8;
+            buffer[offset + 1..offset + 1 + label.len()].copy_from_slice(label.as_bytes());
+            offset += 1 + label.len();
+        }
+        Ok(offset)
+    }
+
+    /// Deserialize a registry written by [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RegistryError> {
+        let [version, flags, used, rest @ ..] = bytes else {
+            return Err(RegistryError::InvalidData);
+        };
+        let mut rest = rest;
+        if *version != FORMAT_VERSION || *flags > 1 {
+            return Err(RegistryError::InvalidData);
+        }
+
+        let mut registry = Self {
+            labels: [const { None }; MAX_IDS],
+            persist: *flags == 1,
+        };
+        for id in all_ids().filter(|id| used & 1 << u8::from(*id) != 0) {
+            let (&len, tail) = rest.split_first().ok_or(RegistryError::InvalidData)?;
+            if tail.len() < usize::from(len) {
+                return Err(RegistryError::InvalidData);
+            }
+            let (label, tail) = tail.split_at(usize::from(len));
+            let label = core::str::from_utf8(label).map_err(|_| RegistryError::InvalidData)?;
+            registry.assign(id, label)?;
+            rest = tail;
+        }
+
+        if !rest.is_empty() {
+            return Err(RegistryError::InvalidData);
+        }
+        Ok(registry)
+    }
+}
+
+fn all_ids() -> impl Iterator<Item = PersonID> {
+    (0..MAX_IDS as u8).map(PersonID::new_unchecked)
+}
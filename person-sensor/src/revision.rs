@@ -0,0 +1,121 @@
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+
+use crate::{CaptureWait, ContinuousCaptureMode, PersonSensor, ReadError};
+
+/// How often the sensor is read while detecting its revision.
+const PROBE_INTERVAL_MS: u32 = 10;
+/// Nominal time between frames of each revision. These are estimates, not figures from the
+/// developer guide, and haven't been measured on every board.
+const V1_FRAME_INTERVAL_MS: u32 = 200;
+const V2_FRAME_INTERVAL_MS: u32 = 100;
+/// The number of frame intervals needed before a revision is inferred.
+const MIN_INTERVALS: usize = 3;
+const MAX_INTERVALS: usize = 16;
+
+/// The hardware revision of the sensor board.
+///
+/// All revisions use the same I2C protocol, registers and frame header, so the revision can't be
+/// read from the sensor. It can either be configured on the builder, or inferred from the frame
+/// rate with [`PersonSensor::detect_revision`].
+///
+/// The only default provided for each revision is a nominal frame interval. The developer guide
+/// doesn't give a frame rate for each revision, so the intervals are estimates that haven't been
+/// measured on every board, and the actual frame rate also depends on whether the ID model is
+/// enabled. The field of view isn't provided, as the developer guide doesn't state it for each
+/// revision either.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SensorRevision {
+    /// The original board, or v1.1. Both revisions behave identically over I2C.
+    V1,
+    /// The v2 board, recognized by its higher frame rate.
+    V2,
+    /// The revision is not known, so conservative defaults are used.
+    #[default]
+    Unknown,
+}
+
+impl SensorRevision {
+    /// The nominal time between frames in continuous mode, which is an estimate. Unknown boards
+    /// are assumed to be as slow as a v1 board.
+    pub fn frame_interval_ms(&self) -> u32 {
+        match self {
+            Self::V1 | Self::Unknown => V1_FRAME_INTERVAL_MS,
+            Self::V2 => V2_FRAME_INTERVAL_MS,
+        }
+    }
+
+    /// How long a single-shot capture waits for results, unless configured on the builder.
+    pub fn capture_wait(&self) -> CaptureWait {
+        CaptureWait::Delay {
+            ms: self.frame_interval_ms(),
+        }
+    }
+
+    /// Infer the revision from the median time between frames, as whichever revision's
+    /// [`frame_interval_ms`](Self::frame_interval_ms) is closest. Ties are considered v1.
+    pub fn from_frame_interval_ms(interval_ms: u32) -> Self {
+        if interval_ms < (V1_FRAME_INTERVAL_MS + V2_FRAME_INTERVAL_MS) / 2 {
+            Self::V2
+        } else {
+            Self::V1
+        }
+    }
+}
+
+impl<I2C, INT, MODE> PersonSensor<I2C, INT, MODE> {
+    /// The revision configured on the builder, or detected in continuous mode with
+    /// [`detect_revision`](PersonSensor::detect_revision).
+    pub fn revision(&self) -> SensorRevision {
+        self.revision
+    }
+}
+
+impl<I2C, INT> PersonSensor<I2C, INT, ContinuousCaptureMode>
+where
+    I2C: I2c,
+{
+    /// Infer the revision of the sensor from how often its results change, for up to
+    /// `duration_ms`.
+    ///
+    /// This is a heuristic that only works while somebody moves in front of the sensor. The
+    /// sensor doesn't report when a new frame is ready, so frames are only noticed when their
+    /// results differ from the previous ones. In a still scene consecutive frames are identical,
+    /// and nothing can be measured.
+    ///
+    /// Returns `None` if fewer than 4 changed frames were seen, leaving the revision on the driver
+    /// as it was. Otherwise the detected revision is stored on the driver.
+    pub async fn detect_revision<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        duration_ms: u32,
+    ) -> Result<Option<SensorRevision>, ReadError<I2C::Error>> {
+        let mut intervals = heapless::Vec::<u32, MAX_INTERVALS>::new();
+        let mut previous = self.read_frame().await?;
+        let mut last_change_ms = None;
+        let mut elapsed_ms = 0;
+
+        while elapsed_ms < duration_ms && !intervals.is_full() {
+            delay.delay_ms(PROBE_INTERVAL_MS).await;
+            elapsed_ms += PROBE_INTERVAL_MS;
+
+            let frame = self.read_frame().await?;
+            if frame == previous {
+                continue;
+            }
+            previous = frame;
+            // The first change only marks the start of a frame
+            if let Some(last_change_ms) = last_change_ms.replace(elapsed_ms) {
+                // Can't fail, the loop stops once the intervals are full
+                _ = intervals.push(elapsed_ms - last_change_ms);
+            }
+        }
+
+        if intervals.len() < MIN_INTERVALS {
+            return Ok(None);
+        }
+        intervals.sort_unstable();
+        self.revision = SensorRevision::from_frame_interval_ms(intervals[intervals.len() / 2]);
+        Ok(Some(self.revision))
+    }
+}
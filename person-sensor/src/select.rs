@@ -0,0 +1,222 @@
+//! Picking "the" face out of a frame.
+//!
+//! [`SelectFaces`] adds helpers to any slice of faces, including [`Detections`](crate::Detections),
+//! to find the largest, most centered or most confident face, or only those facing the sensor or
+//! identified. When the choice drives something physical, such as a pan/tilt mount, use a
+//! [`PrimaryTarget`] instead: it keeps following the same person until somebody else is clearly
+//! a better choice.
+
+use crate::{
+    tracker::{Track, TrackId, Tracker, TrackerConfig},
+    Face, PersonID,
+};
+
+/// Selection helpers for the faces of a frame.
+///
+/// When several faces are equally good, the first of them is selected.
+///
+/// Example:
+/// ```ignore
+/// let faces = person_sensor.get_detections().await?;
+/// if let Some(face) = faces.largest() {
+///     display.highlight(face.bounding_box());
+/// }
+/// let watching = faces.facing().count();
+/// ```
+pub trait SelectFaces {
+    /// The face with the largest bounding box.
+    fn largest(&self) -> Option<&Face>;
+
+    /// The face whose center is closest to the center of the sensor's view.
+    fn most_centered(&self) -> Option<&Face>;
+
+    /// The face with the highest box confidence.
+    fn most_confident(&self) -> Option<&Face>;
+
+    /// The faces looking directly at the sensor.
+    fn facing(&self) -> impl Iterator<Item = &Face>;
+
+    /// The faces recognized as one of the calibrated identities, with their ID.
+    fn identified(&self) -> impl Iterator<Item = (PersonID, &Face)>;
+}
+
+impl SelectFaces for [Face] {
+    fn largest(&self) -> Option<&Face> {
+        best(self, TargetPriority::Largest)
+    }
+
+    fn most_centered(&self) -> Option<&Face> {
+        best(self, TargetPriority::MostCentered)
+    }
+
+    fn most_confident(&self) -> Option<&Face> {
+        best(self, TargetPriority::MostConfident)
+    }
+
+    fn facing(&self) -> impl Iterator<Item = &Face> {
+        self.iter().filter(|face| face.is_facing)
+    }
+
+    fn identified(&self) -> impl Iterator<Item = (PersonID, &Face)> {
+        self.iter()
+            .filter_map(|face| face.id().map(|id| (id, face)))
+    }
+}
+
+fn best(faces: &[Face], priority: TargetPriority) -> Option<&Face> {
+    // `max_by_key` returns the last of equal elements
+    faces.iter().rev().max_by_key(|face| priority.score(face))
+}
+
+/// What makes a face the best choice for a [`PrimaryTarget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TargetPriority {
+    Largest,
+    MostCentered,
+    MostConfident,
+}
+
+impl TargetPriority {
+    /// How good a choice `face` is, higher being better.
+    fn score(&self, face: &Face) -> u32 {
+        match self {
+            Self::Largest => u32::from(face.area()),
+            Self::MostCentered => FARTHEST_FROM_CENTER - squared_distance_from_center(face),
+            Self::MostConfident => u32::from(face.box_confidence),
+        }
+    }
+
+    /// Whether `challenger` is better than `target` by more than `margin_percent`.
+    fn beats(&self, challenger: &Face, target: &Face, margin_percent: u8) -> bool {
+        let margin = 100 + u64::from(margin_percent);
+        match self {
+            Self::MostCentered => {
+                u64::from(squared_distance_from_center(challenger)) * margin
+                    < u64::from(squared_distance_from_center(target)) * 100
+            }
+            _ => u64::from(self.score(challenger)) * 100 > u64::from(self.score(target)) * margin,
+        }
+    }
+}
+
+/// The largest result of [`squared_distance_from_center`].
+const FARTHEST_FROM_CENTER: u32 = 2 * 255 * 255;
+
+/// The squared distance from the center of the face to the center of the view, doubled on each
+/// axis to stay in whole numbers.
+fn squared_distance_from_center(face: &Face) -> u32 {
+    let dx = (u32::from(face.box_left) + u32::from(face.box_right)).abs_diff(255);
+    let dy = (u32::from(face.box_top) + u32::from(face.box_bottom)).abs_diff(255);
+    dx * dx + dy * dy
+}
+
+/// How a [`PrimaryTarget`] chooses its target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TargetConfig {
+    pub priority: TargetPriority,
+    /// Only target faces looking directly at the sensor.
+    pub facing_only: bool,
+    /// How much better than the current target another face must be to take over, in percent of
+    /// the target's area, confidence, or squared distance from the center.
+    pub switch_margin_percent: u8,
+    pub tracker: TrackerConfig,
+}
+
+impl Default for TargetConfig {
+    fn default() -> Self {
+        Self {
+            priority: TargetPriority::Largest,
+            facing_only: false,
+            switch_margin_percent: 20,
+            tracker: TrackerConfig::default(),
+        }
+    }
+}
+
+/// Selects one person to follow, without jumping between people who are about as good a choice.
+///
+/// Faces are followed across frames with a [`Tracker`] of up to `TRACKS` tracks. The target only
+/// changes when another face scores better by the configured margin, when the target is lost, or
+/// when it stops facing the sensor with [`facing_only`](TargetConfig::facing_only). A target
+/// missed for a few frames is kept, and reported with the face it was last seen with.
+///
+/// Example:
+/// ```ignore
+/// let mut target = PrimaryTarget::<4>::new(TargetConfig::default());
+///
+/// loop {
+///     let faces = person_sensor.get_detections().await?;
+///     if let Some(track) = target.update(&faces) {
+///         gimbal.point_at(track.center());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PrimaryTarget<const TRACKS: usize> {
+    config: TargetConfig,
+    tracker: Tracker<TRACKS>,
+    target: Option<TrackId>,
+}
+
+impl<const TRACKS: usize> PrimaryTarget<TRACKS> {
+    pub fn new(config: TargetConfig) -> Self {
+        Self {
+            config,
+            tracker: Tracker::new(config.tracker),
+            target: None,
+        }
+    }
+
+    /// The tracker following the faces.
+    pub fn tracker(&self) -> &Tracker<TRACKS> {
+        &self.tracker
+    }
+
+    /// The current target.
+    pub fn target(&self) -> Option<&Track> {
+        self.tracker.get(self.target?)
+    }
+
+    /// Follow the faces of the next frame, returning the target.
+    pub fn update(&mut self, faces: &[Face]) -> Option<&Track> {
+        self.tracker.update(faces);
+
+        let priority = self.config.priority;
+        let facing_only = self.config.facing_only;
+        let eligible =
+            |track: &Track| track.is_visible() && (!facing_only || track.face().is_facing);
+        let best = self
+            .tracker
+            .tracks()
+            .iter()
+            .filter(|track| eligible(track))
+            .rev()
+            .max_by_key(|track| priority.score(track.face()));
+
+        let current = self.target.and_then(|id| self.tracker.get(id));
+        self.target = match (current, best) {
+            (Some(current), Some(best)) if eligible(current) => {
+                let margin = self.config.switch_margin_percent;
+                if priority.beats(best.face(), current.face(), margin) {
+                    Some(best.id())
+                } else {
+                    Some(current.id())
+                }
+            }
+            // The target looked away
+            (Some(current), Some(best)) if current.is_visible() => Some(best.id()),
+            // Keep a target that was missed while it's tracked, or that nobody can replace
+            (Some(current), _) => Some(current.id()),
+            (None, best) => best.map(Track::id),
+        };
+        self.target()
+    }
+
+    /// Forget the target and every track.
+    pub fn reset(&mut self) {
+        self.tracker.reset();
+        self.target = None;
+    }
+}
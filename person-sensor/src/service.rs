@@ -0,0 +1,257 @@
+//! A task that owns the sensor and shares its detections with other tasks.
+//!
+//! Only one task can own the [`PersonSensor`], but several may want its detections. The
+//! [`SensorService`] owns the sensor, reads it, and publishes detections to a [`DetectionSink`]
+//! such as an embassy-sync [`Watch`](embassy_sync::watch::Watch) or
+//! [`PubSubChannel`](embassy_sync::pubsub::PubSubChannel). Other tasks control the sensor by
+//! sending [`Command`]s through a [`Channel`](embassy_sync::channel::Channel).
+//!
+//! Example:
+//! ```ignore
+//! static DETECTIONS: Watch<CriticalSectionRawMutex, Detections, 2> = Watch::new();
+//! static COMMANDS: Channel<CriticalSectionRawMutex, Command, 4> = Channel::new();
+//!
+//! type Sensor = PersonSensor<I2c<'static, I2C1, Async>, (), ContinuousCaptureMode>;
+//!
+//! #[embassy_executor::task]
+//! async fn sensor_task(sensor: Sensor) {
+//!     let mut service = SensorService::new(sensor, COMMANDS.dyn_receiver(), DETECTIONS.sender());
+//!     service.run(&mut Delay).await
+//! }
+//!
+//! #[embassy_executor::task]
+//! async fn display_task() {
+//!     let mut detections = DETECTIONS.receiver().unwrap();
+//!     loop {
+//!         let faces = detections.changed().await;
+//!         // Draw the faces
+//!     }
+//! }
+//! ```
+
+use embassy_futures::select::{select, Either};
+use embassy_sync::{
+    blocking_mutex::raw::RawMutex,
+    channel::DynamicReceiver,
+    pubsub::{DynImmediatePublisher, ImmediatePublisher},
+    watch::{DynSender, Sender},
+};
+use embedded_hal_async::{delay::DelayNs, digital::Wait, i2c::I2c};
+
+use crate::{
+    command::{Command, CommandExecutor},
+    power::ManagedSensor,
+    ContinuousCaptureMode, Detections, PersonSensor, PersonSensorMode, ReadError, StandbyMode,
+};
+
+/// Receives the detections published by a [`SensorService`].
+///
+/// Any `FnMut(&Detections)` closure is a sink. Publishing never waits: a
+/// [`Watch`](embassy_sync::watch::Watch) only keeps the latest detections, and subscribers of a
+/// [`PubSubChannel`](embassy_sync::pubsub::PubSubChannel) that fall behind miss the oldest ones.
+pub trait DetectionSink {
+    fn publish(&mut self, detections: &Detections);
+}
+
+impl<F> DetectionSink for F
+where
+    F: FnMut(&Detections),
+{
+    fn publish(&mut self, detections: &Detections) {
+        self(detections)
+    }
+}
+
+impl<M: RawMutex, const N: usize> DetectionSink for Sender<'_, M, Detections, N> {
+    fn publish(&mut self, detections: &Detections) {
+        self.send(detections.clone())
+    }
+}
+
+impl DetectionSink for DynSender<'_, Detections> {
+    fn publish(&mut self, detections: &Detections) {
+        self.send(detections.clone())
+    }
+}
+
+impl<M: RawMutex, const CAP: usize, const SUBS: usize, const PUBS: usize> DetectionSink
+    for ImmediatePublisher<'_, M, Detections, CAP, SUBS, PUBS>
+{
+    fn publish(&mut self, detections: &Detections) {
+        self.publish_immediate(detections.clone())
+    }
+}
+
+impl DetectionSink for DynImmediatePublisher<'_, Detections> {
+    fn publish(&mut self, detections: &Detections) {
+        self.publish_immediate(detections.clone())
+    }
+}
+
+/// Owns the sensor, publishes its detections and executes the commands sent to it.
+///
+/// In continuous mode the sensor is read every poll interval, which defaults to one frame for
+/// the configured [`SensorRevision`](crate::SensorRevision). Detections are only published when
+/// they change, and always after a [`Command::CaptureOnce`]. In standby the service waits for
+/// commands.
+pub struct SensorService<'a, I2C, INT, SINK> {
+    executor: CommandExecutor<I2C, INT>,
+    commands: DynamicReceiver<'a, Command>,
+    sink: SINK,
+    poll_interval_ms: u32,
+    last: Option<Detections>,
+}
+
+impl<'a, I2C, INT, SINK> SensorService<'a, I2C, INT, SINK>
+where
+    I2C: I2c,
+    SINK: DetectionSink,
+{
+    /// Create a service from a sensor in continuous mode.
+    pub fn new(
+        sensor: PersonSensor<I2C, INT, ContinuousCaptureMode>,
+        commands: DynamicReceiver<'a, Command>,
+        sink: SINK,
+    ) -> Self {
+        Self::with_executor(CommandExecutor::new(sensor), commands, sink)
+    }
+
+    /// Create a service from a sensor in standby mode.
+    pub fn from_standby(
+        sensor: PersonSensor<I2C, INT, StandbyMode>,
+        commands: DynamicReceiver<'a, Command>,
+        sink: SINK,
+    ) -> Self {
+        Self::with_executor(CommandExecutor::from_standby(sensor), commands, sink)
+    }
+
+    fn with_executor(
+        executor: CommandExecutor<I2C, INT>,
+        commands: DynamicReceiver<'a, Command>,
+        sink: SINK,
+    ) -> Self {
+        Self {
+            poll_interval_ms: executor.sensor.revision().frame_interval_ms(),
+            executor,
+            commands,
+            sink,
+            last: None,
+        }
+    }
+
+    /// Sets how often the sensor is read in continuous mode.
+    pub fn with_poll_interval_ms(self, poll_interval_ms: u32) -> Self {
+        Self {
+            poll_interval_ms,
+            ..self
+        }
+    }
+
+    /// The mode the sensor is currently in.
+    pub fn mode(&self) -> PersonSensorMode {
+        self.executor.mode()
+    }
+
+    /// Wait for the next poll or command, and handle it.
+    ///
+    /// A command received while waiting to poll is executed instead, and the next step waits for
+    /// a full poll interval again.
+    pub async fn step<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), ReadError<I2C::Error>> {
+        match self.executor.mode() {
+            PersonSensorMode::Standby => {
+                let command = self.commands.receive().await;
+                self.execute(command, delay).await
+            }
+            PersonSensorMode::Continuous => {
+                match select(
+                    self.commands.receive(),
+                    delay.delay_ms(self.poll_interval_ms),
+                )
+                .await
+                {
+                    Either::First(command) => self.execute(command, delay).await,
+                    Either::Second(()) => self.read(false).await,
+                }
+            }
+        }
+    }
+
+    /// Run the service forever.
+    ///
+    /// Errors are ignored, as the sensor is read again on the next poll. Call
+    /// [`step`](Self::step) in a loop to handle them.
+    pub async fn run<D: DelayNs>(&mut self, delay: &mut D) -> ! {
+        loop {
+            _ = self.step(delay).await;
+        }
+    }
+
+    /// Execute a single command, as if it had been received.
+    pub async fn execute<D: DelayNs>(
+        &mut self,
+        command: Command,
+        delay: &mut D,
+    ) -> Result<(), ReadError<I2C::Error>> {
+        if let Some(detections) = self.executor.execute(command, delay).await? {
+            self.publish(detections, true);
+        }
+        Ok(())
+    }
+
+    /// Stop the service and return the sensor in its current mode.
+    pub fn release(self) -> ManagedSensor<I2C, INT> {
+        self.executor.release()
+    }
+
+    async fn read(&mut self, always_publish: bool) -> Result<(), ReadError<I2C::Error>> {
+        let detections = self.executor.latest_results().await?;
+        self.publish(detections, always_publish);
+        Ok(())
+    }
+
+    fn publish(&mut self, detections: Detections, always_publish: bool) {
+        if always_publish || self.last.as_ref() != Some(&detections) {
+            self.sink.publish(&detections);
+            self.last = Some(detections);
+        }
+    }
+}
+
+impl<I2C, INT, SINK> SensorService<'_, I2C, INT, SINK>
+where
+    I2C: I2c,
+    INT: Wait,
+    SINK: DetectionSink,
+{
+    /// Like [`step`](Self::step), but while nobody is in view the sensor isn't polled until the
+    /// interrupt pin goes high.
+    ///
+    /// Interrupt errors are treated as the pin going high, so the sensor is read instead.
+    pub async fn step_with_interrupt<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<(), ReadError<I2C::Error>> {
+        let nobody_in_view = self.last.as_ref().is_some_and(|last| last.is_empty());
+        if self.executor.mode() == PersonSensorMode::Standby || !nobody_in_view {
+            return self.step(delay).await;
+        }
+
+        match select(
+            self.commands.receive(),
+            self.executor.sensor.interrupt.wait_for_high(),
+        )
+        .await
+        {
+            Either::First(command) => self.execute(command, delay).await,
+            Either::Second(_) => self.read(false).await,
+        }
+    }
+
+    /// Run the service forever, using the interrupt pin as in
+    /// [`step_with_interrupt`](Self::step_with_interrupt).
+    pub async fn run_with_interrupt<D: DelayNs>(&mut self, delay: &mut D) -> ! {
+        loop {
+            _ = self.step_with_interrupt(delay).await;
+        }
+    }
+}
@@ -0,0 +1,651 @@
+//! A simulated sensor for testing code that uses the driver without hardware.
+//!
+//! [`SimulatedSensor`] models the sensor's registers, produces valid frames from a script of
+//! scenes, and records every command written to it. The driver talks to it through the borrowed
+//! handles returned by [`SimulatedSensor::bus`] and [`SimulatedSensor::interrupt`], so the test
+//! can keep scripting and inspecting the sensor while the driver owns the handles.
+//!
+//! Example:
+//! ```ignore
+//! let sim = SimulatedSensor::new();
+//! sim.push_scene(&[face]);
+//!
+//! let mut sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+//!     .with_interrupt(sim.interrupt())
+//!     .build()
+//!     .await?;
+//!
+//! sensor.wait_for_person().await?;
+//! assert_eq!(sensor.get_detections().await?.len(), 1);
+//! assert_eq!(sim.commands(), [SimCommand::SetMode(1), SimCommand::EnableIdModel(true)]);
+//! ```
+//!
+//! Faults can be injected into specific transactions with [`SimulatedSensor::inject_fault`], to
+//! exercise how firmware handles a flaky sensor or bus.
+//!
+//! Single-shot captures complete after the latency set with
+//! [`SimulatedSensor::set_inference_latency_ms`]. Time only passes in the simulation through the
+//! delay returned by [`SimulatedSensor::delay`], so pass it to the driver wherever it waits for a
+//! capture.
+
+use alloc::{collections::VecDeque, vec::Vec};
+use core::{
+    cell::RefCell,
+    convert::Infallible,
+    future::poll_fn,
+    task::{Poll, Waker},
+};
+
+use embedded_hal_async::{
+    delay::DelayNs,
+    digital::Wait,
+    i2c::{self, ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation, SevenBitAddress},
+};
+
+use crate::{encode_frame, registry::MAX_IDS, Face, PersonID, Recognition};
+
+const ADDRESS: u8 = 0x62;
+/// Frames without a face after which a pending label is discarded.
+const LABEL_TIMEOUT_FRAMES: u8 = 2;
+
+/// The values of the sensor's configuration registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimRegisters {
+    /// 0 for standby, 1 for continuous capture.
+    pub mode: u8,
+    pub id_model_enabled: bool,
+    pub persist_ids: bool,
+    pub indicator_enabled: bool,
+}
+
+impl Default for SimRegisters {
+    /// The values after the sensor powers on.
+    fn default() -> Self {
+        Self {
+            mode: 1,
+            id_model_enabled: true,
+            persist_ids: true,
+            indicator_enabled: true,
+        }
+    }
+}
+
+/// A register write received by the [`SimulatedSensor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimCommand {
+    SetMode(u8),
+    EnableIdModel(bool),
+    SingleShot,
+    LabelNextId(u8),
+    PersistIds(bool),
+    EraseIds,
+    SetIndicator(bool),
+    /// A write to a register the sensor doesn't have.
+    Unknown {
+        register: u8,
+        value: u8,
+    },
+}
+
+impl SimCommand {
+    fn new(register: u8, value: u8) -> Self {
+        match register {
+            0x01 => Self::SetMode(value),
+            0x02 => Self::EnableIdModel(value != 0),
+            0x03 => Self::SingleShot,
+            0x04 => Self::LabelNextId(value),
+            0x05 => Self::PersistIds(value != 0),
+            0x06 => Self::EraseIds,
+            0x07 => Self::SetIndicator(value != 0),
+            register => Self::Unknown { register, value },
+        }
+    }
+}
+
+/// Errors returned by the simulated bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimError {
+    /// The transaction was addressed to a device other than the sensor, or the sensor did not
+    /// respond because of an injected fault.
+    Nack,
+}
+
+/// A fault that the [`SimulatedSensor`] injects into a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimFault {
+    /// The sensor does not acknowledge the transaction, which has no effect.
+    Nack,
+    /// The sensor reboots before the transaction, and does not acknowledge it while restarting.
+    Reboot,
+    /// Frames read in the transaction have an invalid checksum.
+    CorruptChecksum,
+    /// The sensor stops responding after `len` bytes of each read. The rest of the buffer reads
+    /// as `0xff`, as nothing drives the bus.
+    TruncatedRead { len: usize },
+    /// Frames read in the transaction report `count` faces, with a valid checksum. Records beyond
+    /// the faces in view are zeroed.
+    WrongFaceCount(u8),
+}
+
+impl i2c::Error for SimError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Nack => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    registers: SimRegisters,
+    calibrated: [bool; MAX_IDS],
+    /// The ID to label and the number of frames without a face since it was requested.
+    pending_label: Option<(u8, u8)>,
+    scenes: VecDeque<Vec<Face>>,
+    scene: Vec<Face>,
+    frame: [u8; 39],
+    /// Whether `frame` was produced by [`SimulatedSensor::advance`] or on power on, and hasn't
+    /// been read yet.
+    fresh: bool,
+    /// Nanoseconds passed in the simulation.
+    now_ns: u64,
+    inference_latency_ns: u64,
+    /// When the single-shot capture in progress completes.
+    capture_done_ns: Option<u64>,
+    /// Whether `frame` was produced by a single-shot capture since the mode was last set.
+    captured: bool,
+    frames: usize,
+    reads: usize,
+    commands: Vec<SimCommand>,
+    interrupt_high: bool,
+    rising_edges: usize,
+    falling_edges: usize,
+    /// Holds the interrupt line at a fixed level, regardless of detections.
+    interrupt_stuck: Option<bool>,
+    wakers: Vec<Waker>,
+    transactions: usize,
+    reboots: usize,
+    /// Faults and the index of the transaction they are injected into.
+    faults: Vec<(usize, SimFault)>,
+}
+
+impl State {
+    fn write(&mut self, register: u8, value: u8) {
+        let command = SimCommand::new(register, value);
+        self.commands.push(command);
+        match command {
+            SimCommand::SetMode(mode) => {
+                self.registers.mode = mode;
+                self.captured = false;
+            }
+            SimCommand::EnableIdModel(enabled) => self.registers.id_model_enabled = enabled,
+            SimCommand::SingleShot if self.registers.mode == 0 => {
+                self.captured = false;
+                self.capture_done_ns = Some(self.now_ns + self.inference_latency_ns);
+                self.elapse(self.now_ns);
+            }
+            SimCommand::LabelNextId(id) if usize::from(id) < MAX_IDS => {
+                self.pending_label = Some((id, 0))
+            }
+            SimCommand::PersistIds(persist) => self.registers.persist_ids = persist,
+            SimCommand::EraseIds => self.calibrated = [false; MAX_IDS],
+            SimCommand::SetIndicator(enabled) => self.registers.indicator_enabled = enabled,
+            _ => {}
+        }
+        self.update_interrupt();
+    }
+
+    fn read(&mut self, buffer: &mut [u8], faults: &[SimFault]) {
+        self.reads += 1;
+        if self.registers.mode == 1 && !self.fresh {
+            self.next_frame();
+            self.update_interrupt();
+        }
+        self.fresh = false;
+
+        let mut frame = self.frame;
+        let mut truncated_len = frame.len();
+        for fault in faults {
+            match *fault {
+                SimFault::WrongFaceCount(count) => {
+                    frame[4] = count;
+                    let checksum = crc16::State::<crc16::MCRF4XX>::calculate(&frame[..37]);
+                    frame[37..].copy_from_slice(&checksum.to_le_bytes());
+                }
+                SimFault::CorruptChecksum => frame[37] ^= 0xff,
+                SimFault::TruncatedRead { len } => truncated_len = truncated_len.min(len),
+                SimFault::Nack | SimFault::Reboot => {}
+            }
+        }
+
+        let len = buffer.len().min(truncated_len);
+        buffer[..len].copy_from_slice(&frame[..len]);
+        buffer[len..].fill(if truncated_len < frame.len() { 0xff } else { 0 });
+    }
+
+    /// Restarts the sensor, resetting its registers. Calibrations are kept only if IDs were
+    /// persisted.
+    fn reboot(&mut self) {
+        if !self.registers.persist_ids {
+            self.calibrated = [false; MAX_IDS];
+        }
+        self.registers = SimRegisters::default();
+        self.pending_label = None;
+        self.capture_done_ns = None;
+        self.captured = false;
+        self.frame = encode_frame(&[]);
+        self.fresh = true;
+        self.reboots += 1;
+        self.update_interrupt();
+    }
+
+    /// Advances the simulation to `now_ns`, completing the single-shot capture in progress if it
+    /// is due by then.
+    fn elapse(&mut self, now_ns: u64) {
+        self.now_ns = self.now_ns.max(now_ns);
+        if self.capture_done_ns.is_some_and(|done| done <= self.now_ns) {
+            self.capture_done_ns = None;
+            self.next_frame();
+            self.captured = true;
+            self.update_interrupt();
+        }
+    }
+
+    /// Runs inference on the next scene, or the current one if the script has run out.
+    fn next_frame(&mut self) {
+        if let Some(scene) = self.scenes.pop_front() {
+            self.scene = scene;
+        }
+        self.frames += 1;
+
+        if let Some((id, frames_without_face)) = self.pending_label {
+            if !self.scene.is_empty() {
+                self.calibrated[usize::from(id)] = true;
+                self.pending_label = None;
+            } else if frames_without_face + 1 >= LABEL_TIMEOUT_FRAMES {
+                self.pending_label = None;
+            } else {
+                self.pending_label = Some((id, frames_without_face + 1));
+            }
+        }
+
+        let faces: Vec<Face> = self.scene.iter().map(|face| self.observe(face)).collect();
+        self.frame = encode_frame(&faces);
+    }
+
+    /// The face as reported by the sensor, given its current configuration.
+    fn observe(&self, face: &Face) -> Face {
+        let mut face = face.clone();
+        if let Recognition::Identified { id, .. } = face.recognition {
+            if !self.registers.id_model_enabled || !self.calibrated[usize::from(u8::from(id))] {
+                face.recognition = Recognition::Unknown;
+            }
+        }
+        face
+    }
+
+    fn update_interrupt(&mut self) {
+        let high = self
+            .interrupt_stuck
+            .unwrap_or((self.registers.mode == 1 || self.captured) && self.frame[4] > 0);
+        if high != self.interrupt_high {
+            self.interrupt_high = high;
+            if high {
+                self.rising_edges += 1;
+            } else {
+                self.falling_edges += 1;
+            }
+            for waker in self.wakers.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// A simulated person sensor.
+///
+/// The sensor runs inference on the next scripted scene for each frame, then keeps reporting the
+/// last scene once the script runs out. In continuous mode every read returns a new frame, unless
+/// one was already produced with [`advance`](Self::advance), or the sensor has just powered on
+/// and its first, empty frame hasn't been read yet. In standby mode a new frame is only produced
+/// by a single-shot capture, and reads return the latest one. Captures complete after the
+/// inference latency, and reads return the previous frame until then.
+///
+/// The interrupt pin is high while the latest frame has faces. In standby mode it only rises for
+/// frames from a single-shot capture, and drops while the next capture runs.
+///
+/// Like the real sensor, faces are only reported as identified if their ID has been calibrated and
+/// the ID model is enabled. IDs are calibrated by labeling the next frame that contains a face, or
+/// directly with [`calibrate`](Self::calibrate).
+#[derive(Debug)]
+pub struct SimulatedSensor {
+    state: RefCell<State>,
+}
+
+impl Default for SimulatedSensor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulatedSensor {
+    /// A powered on sensor with no faces in view and no calibrated IDs.
+    pub fn new() -> Self {
+        Self {
+            state: RefCell::new(State {
+                registers: SimRegisters::default(),
+                calibrated: [false; MAX_IDS],
+                pending_label: None,
+                scenes: VecDeque::new(),
+                scene: Vec::new(),
+                frame: encode_frame(&[]),
+                fresh: true,
+                now_ns: 0,
+                inference_latency_ns: 0,
+                capture_done_ns: None,
+                captured: false,
+                frames: 0,
+                reads: 0,
+                commands: Vec::new(),
+                interrupt_high: false,
+                rising_edges: 0,
+                falling_edges: 0,
+                interrupt_stuck: None,
+                wakers: Vec::new(),
+                transactions: 0,
+                reboots: 0,
+                faults: Vec::new(),
+            }),
+        }
+    }
+
+    /// The I2C bus the sensor is connected to.
+    pub fn bus(&self) -> SimBus<'_> {
+        SimBus { sim: self }
+    }
+
+    /// The sensor's interrupt pin, which is high while a face is detected.
+    pub fn interrupt(&self) -> SimInterrupt<'_> {
+        SimInterrupt { sim: self }
+    }
+
+    /// A delay that advances the simulation's time instead of waiting.
+    pub fn delay(&self) -> SimDelay<'_> {
+        SimDelay { sim: self }
+    }
+
+    /// The time passed in the simulation, through delays from [`delay`](Self::delay).
+    pub fn now_ms(&self) -> u64 {
+        self.state.borrow().now_ns / 1_000_000
+    }
+
+    /// Set how long a single-shot capture takes to complete. Captures complete immediately by
+    /// default.
+    pub fn set_inference_latency_ms(&self, ms: u32) {
+        self.state.borrow_mut().inference_latency_ns = u64::from(ms) * 1_000_000;
+    }
+
+    /// Add a scene to the script. Faces are reported in the order given, encoded as by
+    /// [`encode_frame`].
+    pub fn push_scene(&self, faces: &[Face]) {
+        self.state.borrow_mut().scenes.push_back(faces.to_vec());
+    }
+
+    /// Add several scenes to the script.
+    pub fn push_scenes<'a>(&self, scenes: impl IntoIterator<Item = &'a [Face]>) {
+        for scene in scenes {
+            self.push_scene(scene);
+        }
+    }
+
+    /// The number of scripted scenes that haven't been captured yet.
+    pub fn scenes_remaining(&self) -> usize {
+        self.state.borrow().scenes.len()
+    }
+
+    /// Produce the next frame in continuous mode, as if the sensor finished running inference.
+    /// The next read returns this frame. Does nothing in standby mode.
+    pub fn advance(&self) {
+        let mut state = self.state.borrow_mut();
+        if state.registers.mode == 1 {
+            state.next_frame();
+            state.fresh = true;
+            state.update_interrupt();
+        }
+    }
+
+    /// Mark `id` as calibrated, as if it had been labeled earlier.
+    pub fn calibrate(&self, id: PersonID) {
+        self.state.borrow_mut().calibrated[usize::from(u8::from(id))] = true;
+    }
+
+    /// Whether the sensor has been calibrated on `id`.
+    pub fn is_calibrated(&self, id: PersonID) -> bool {
+        self.state.borrow().calibrated[usize::from(u8::from(id))]
+    }
+
+    /// The ID that will be assigned to the next face, if a label was requested.
+    pub fn pending_label(&self) -> Option<PersonID> {
+        let state = self.state.borrow();
+        state
+            .pending_label
+            .map(|(id, _)| PersonID::new_unchecked(id))
+    }
+
+    /// The current values of the configuration registers.
+    pub fn registers(&self) -> SimRegisters {
+        self.state.borrow().registers
+    }
+
+    /// The latest raw frame produced by the sensor.
+    pub fn frame(&self) -> [u8; 39] {
+        self.state.borrow().frame
+    }
+
+    /// The number of frames the sensor has produced.
+    pub fn frames(&self) -> usize {
+        self.state.borrow().frames
+    }
+
+    /// The number of reads from the sensor.
+    pub fn reads(&self) -> usize {
+        self.state.borrow().reads
+    }
+
+    /// Every command written to the sensor, in order.
+    pub fn commands(&self) -> Vec<SimCommand> {
+        self.state.borrow().commands.clone()
+    }
+
+    /// Forget the commands recorded so far.
+    pub fn clear_commands(&self) {
+        self.state.borrow_mut().commands.clear();
+    }
+
+    /// The current level of the interrupt pin.
+    pub fn interrupt_high(&self) -> bool {
+        self.state.borrow().interrupt_high
+    }
+
+    /// Inject `fault` into the transaction with the given index, counting from 0 for the first
+    /// transaction on the bus. Several faults can be injected into the same transaction.
+    pub fn inject_fault(&self, transaction: usize, fault: SimFault) {
+        self.state.borrow_mut().faults.push((transaction, fault));
+    }
+
+    /// Inject `fault` into the next transaction.
+    pub fn inject_next(&self, fault: SimFault) {
+        let transaction = self.transactions();
+        self.inject_fault(transaction, fault);
+    }
+
+    /// The number of transactions on the bus so far, including failed ones.
+    pub fn transactions(&self) -> usize {
+        self.state.borrow().transactions
+    }
+
+    /// Hold the interrupt line at `level` regardless of detections, or release it with `None`.
+    pub fn set_interrupt_stuck(&self, level: Option<bool>) {
+        let mut state = self.state.borrow_mut();
+        state.interrupt_stuck = level;
+        state.update_interrupt();
+    }
+
+    /// Restart the sensor, as after a brownout. Registers return to their power-on values, and
+    /// calibrations are kept only if IDs were persisted. The script continues where it left off.
+    pub fn reboot(&self) {
+        self.state.borrow_mut().reboot();
+    }
+
+    /// The number of times the sensor has rebooted.
+    pub fn reboots(&self) -> usize {
+        self.state.borrow().reboots
+    }
+
+    /// Waits until `ready` returns true, re-checking whenever the sensor's state changes.
+    async fn wait_until(&self, ready: impl Fn(&State) -> bool) {
+        poll_fn(|cx| {
+            let mut state = self.state.borrow_mut();
+            if ready(&state) {
+                Poll::Ready(())
+            } else {
+                if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    state.wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+/// The I2C bus of a [`SimulatedSensor`].
+#[derive(Debug, Clone, Copy)]
+pub struct SimBus<'a> {
+    sim: &'a SimulatedSensor,
+}
+
+impl ErrorType for SimBus<'_> {
+    type Error = SimError;
+}
+
+impl I2c<SevenBitAddress> for SimBus<'_> {
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let mut state = self.sim.state.borrow_mut();
+        let transaction = state.transactions;
+        state.transactions += 1;
+        if address != ADDRESS {
+            return Err(SimError::Nack);
+        }
+
+        let mut faults = Vec::new();
+        state.faults.retain(|(index, fault)| {
+            if *index == transaction {
+                faults.push(*fault);
+            }
+            *index > transaction
+        });
+        if faults.contains(&SimFault::Reboot) {
+            state.reboot();
+        }
+        if faults
+            .iter()
+            .any(|fault| matches!(fault, SimFault::Nack | SimFault::Reboot))
+        {
+            return Err(SimError::Nack);
+        }
+
+        for operation in operations {
+            match operation {
+                Operation::Read(buffer) => state.read(buffer, &faults),
+                Operation::Write([register, value, ..]) => state.write(*register, *value),
+                // Setting the register pointer alone has no effect
+                Operation::Write(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The interrupt pin of a [`SimulatedSensor`].
+#[derive(Debug, Clone, Copy)]
+pub struct SimInterrupt<'a> {
+    sim: &'a SimulatedSensor,
+}
+
+impl embedded_hal::digital::ErrorType for SimInterrupt<'_> {
+    type Error = Infallible;
+}
+
+impl Wait for SimInterrupt<'_> {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.sim.wait_until(|state| state.interrupt_high).await;
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.sim.wait_until(|state| !state.interrupt_high).await;
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        let edges = self.sim.state.borrow().rising_edges;
+        self.sim
+            .wait_until(|state| state.rising_edges != edges)
+            .await;
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        let edges = self.sim.state.borrow().falling_edges;
+        self.sim
+            .wait_until(|state| state.falling_edges != edges)
+            .await;
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        let edges = {
+            let state = self.sim.state.borrow();
+            state.rising_edges + state.falling_edges
+        };
+        self.sim
+            .wait_until(|state| state.rising_edges + state.falling_edges != edges)
+            .await;
+        Ok(())
+    }
+}
+
+/// A delay that advances the time of a [`SimulatedSensor`].
+///
+/// Completing a capture during the delay wakes anything waiting on the interrupt pin before the
+/// delay finishes, so a driver racing the two sees the interrupt first.
+#[derive(Debug, Clone, Copy)]
+pub struct SimDelay<'a> {
+    sim: &'a SimulatedSensor,
+}
+
+impl DelayNs for SimDelay<'_> {
+    async fn delay_ns(&mut self, ns: u32) {
+        let end_ns = self.sim.state.borrow().now_ns + u64::from(ns);
+        poll_fn(|cx| {
+            let mut state = self.sim.state.borrow_mut();
+            match state.capture_done_ns {
+                Some(done) if done < end_ns => {
+                    state.elapse(done);
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+                _ => {
+                    state.elapse(end_ns);
+                    Poll::Ready(())
+                }
+            }
+        })
+        .await
+    }
+}
@@ -0,0 +1,322 @@
+//! Smoothing the jitter out of bounding boxes.
+//!
+//! The edges of a face's bounding box move by a few units between frames even when the person
+//! stands still. The [`BoxSmoother`] follows each person across frames and filters the center
+//! and size of their box, estimating how fast it moves. The estimate can be extrapolated between
+//! frames, to drive a servo more often than the sensor reports.
+//!
+//! Example:
+//! ```ignore
+//! let clock = || Instant::now().as_millis();
+//! let mut smoother = BoxSmoother::<_, 4>::new(clock, SmoothingConfig::default());
+//! let mut target = None;
+//!
+//! loop {
+//!     match select(person_sensor.get_detections(), Timer::after_millis(20)).await {
+//!         Either::First(Ok(faces)) => {
+//!             target = smoother.update(&faces).first().map(|estimate| estimate.track);
+//!         }
+//!         _ => {}
+//!     }
+//!     if let Some(estimate) = target.and_then(|track| smoother.predict(track)) {
+//!         servo.point_at(estimate.x, estimate.y);
+//!     }
+//! }
+//! ```
+
+use crate::{
+    tracker::{TrackId, Tracker, TrackerConfig},
+    BoundingBox, Clock, Face,
+};
+
+/// The filter used by the [`BoxSmoother`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Smoothing {
+    /// An exponential moving average. `alpha` is the weight of each new frame, from 0 to 1: lower
+    /// values are smoother, but lag further behind.
+    Ema { alpha: f32 },
+    /// A Kalman filter assuming each face moves at a constant velocity.
+    Kalman {
+        /// How much faces accelerate, as the variance of the change in velocity over a second in
+        /// units²/s³. Higher values follow changes in direction faster.
+        process_noise: f32,
+        /// How much the box jitters, as its variance in units². Higher values are smoother.
+        measurement_noise: f32,
+    },
+}
+
+/// Configuration for the [`BoxSmoother`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SmoothingConfig {
+    pub smoothing: Smoothing,
+    pub tracker: TrackerConfig,
+}
+
+impl Default for SmoothingConfig {
+    fn default() -> Self {
+        Self {
+            smoothing: Smoothing::Kalman {
+                process_noise: 1_000.0,
+                measurement_noise: 9.0,
+            },
+            tracker: TrackerConfig::default(),
+        }
+    }
+}
+
+/// The smoothed position and size of a face, reported by the [`BoxSmoother`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SmoothedBox {
+    pub track: TrackId,
+    /// The horizontal center of the box.
+    pub x: f32,
+    /// The vertical center of the box.
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// How fast the center moves horizontally, in units per second.
+    pub velocity_x: f32,
+    /// How fast the center moves vertically, in units per second.
+    pub velocity_y: f32,
+}
+
+impl SmoothedBox {
+    /// The box rounded to sensor coordinates, and clipped to the sensor's view.
+    pub fn bounding_box(&self) -> BoundingBox {
+        let edge = |value: f32| (value + 0.5).clamp(0.0, 255.0) as u8;
+        BoundingBox {
+            left: edge(self.x - self.width / 2.0),
+            top: edge(self.y - self.height / 2.0),
+            right: edge(self.x + self.width / 2.0),
+            bottom: edge(self.y + self.height / 2.0),
+        }
+    }
+}
+
+/// A single filtered value and its rate of change.
+#[derive(Debug, Clone, Copy)]
+struct Axis {
+    value: f32,
+    velocity: f32,
+    /// The covariance of the value and velocity, only used by the Kalman filter.
+    covariance: [[f32; 2]; 2],
+}
+
+/// The initial variance of the velocity, in units²/s², large enough to follow a face that is
+/// already moving when it appears.
+const INITIAL_VELOCITY_VARIANCE: f32 = 10_000.0;
+
+impl Axis {
+    fn new(value: f32, smoothing: &Smoothing) -> Self {
+        let value_variance = match smoothing {
+            Smoothing::Ema { .. } => 0.0,
+            Smoothing::Kalman {
+                measurement_noise, ..
+            } => *measurement_noise,
+        };
+        Self {
+            value,
+            velocity: 0.0,
+            covariance: [[value_variance, 0.0], [0.0, INITIAL_VELOCITY_VARIANCE]],
+        }
+    }
+
+    fn predict(&self, dt: f32) -> f32 {
+        self.value + self.velocity * dt
+    }
+
+    fn update(&mut self, measurement: f32, dt: f32, smoothing: &Smoothing) {
+        match *smoothing {
+            Smoothing::Ema { alpha } => {
+                let previous = self.value;
+                self.value += alpha * (measurement - self.value);
+                if dt > 0.0 {
+                    let velocity = (self.value - previous) / dt;
+                    self.velocity += alpha * (velocity - self.velocity);
+                }
+            }
+            Smoothing::Kalman {
+                process_noise,
+                measurement_noise,
+            } => {
+                // Predict with the constant velocity model
+                let [[p00, p01], [p10, p11]] = self.covariance;
+                let (dt2, dt3) = (dt * dt, dt * dt * dt);
+                let p00 = p00 + dt * (p10 + p01) + dt2 * p11 + process_noise * dt3 / 3.0;
+                let p01 = p01 + dt * p11 + process_noise * dt2 / 2.0;
+                let p10 = p10 + dt * p11 + process_noise * dt2 / 2.0;
+                let p11 = p11 + process_noise * dt;
+                self.value = self.predict(dt);
+
+                // Correct with the measurement
+                let innovation = measurement - self.value;
+                let variance = p00 + measurement_noise;
+                if variance <= 0.0 {
+                    self.value = measurement;
+                    return;
+                }
+                let (k0, k1) = (p00 / variance, p10 / variance);
+                self.value += k0 * innovation;
+                self.velocity += k1 * innovation;
+                self.covariance = [
+                    [(1.0 - k0) * p00, (1.0 - k0) * p01],
+                    [p10 - k1 * p00, p11 - k1 * p01],
+                ];
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Estimate {
+    track: TrackId,
+    updated_ms: u64,
+    /// The horizontal center, vertical center, width and height.
+    axes: [Axis; 4],
+}
+
+impl Estimate {
+    fn measurements(face: &Face) -> [f32; 4] {
+        let bounding_box = face.bounding_box();
+        [
+            (f32::from(bounding_box.left) + f32::from(bounding_box.right)) / 2.0,
+            (f32::from(bounding_box.top) + f32::from(bounding_box.bottom)) / 2.0,
+            f32::from(bounding_box.width()),
+            f32::from(bounding_box.height()),
+        ]
+    }
+
+    fn smoothed_box(&self, dt: f32) -> SmoothedBox {
+        let [x, y, width, height] = self.axes;
+        SmoothedBox {
+            track: self.track,
+            x: x.predict(dt),
+            y: y.predict(dt),
+            width: width.predict(dt).max(0.0),
+            height: height.predict(dt).max(0.0),
+            velocity_x: x.velocity,
+            velocity_y: y.velocity,
+        }
+    }
+}
+
+/// Smooths the boxes of up to `TRACKS` people at once.
+///
+/// Faces are followed across frames with a [`Tracker`], and each is filtered separately. Time is
+/// read from `CLOCK` on every update and prediction, so velocities stay correct when frames are
+/// missed or read at an irregular rate.
+#[derive(Debug, Clone)]
+pub struct BoxSmoother<CLOCK, const TRACKS: usize> {
+    clock: CLOCK,
+    smoothing: Smoothing,
+    tracker: Tracker<TRACKS>,
+    estimates: heapless::Vec<Estimate, TRACKS>,
+}
+
+impl<CLOCK, const TRACKS: usize> BoxSmoother<CLOCK, TRACKS>
+where
+    CLOCK: Clock,
+{
+    pub fn new(clock: CLOCK, config: SmoothingConfig) -> Self {
+        Self {
+            clock,
+            smoothing: config.smoothing,
+            tracker: Tracker::new(config.tracker),
+            estimates: heapless::Vec::new(),
+        }
+    }
+
+    /// The tracker following the faces.
+    pub fn tracker(&self) -> &Tracker<TRACKS> {
+        &self.tracker
+    }
+
+    /// Follow and filter the faces of the next frame, returning the smoothed boxes of the people
+    /// detected in it.
+    pub fn update(&mut self, faces: &[Face]) -> heapless::Vec<SmoothedBox, TRACKS> {
+        let now = self.clock.now_ms();
+        for track in self.tracker.update(faces) {
+            self.estimates
+                .retain(|estimate| estimate.track != track.id());
+        }
+
+        let mut boxes = heapless::Vec::new();
+        for track in self
+            .tracker
+            .tracks()
+            .iter()
+            .filter(|track| track.is_visible())
+        {
+            let measurements = Estimate::measurements(track.face());
+            let smoothed = match self
+                .estimates
+                .iter_mut()
+                .find(|estimate| estimate.track == track.id())
+            {
+                Some(estimate) => {
+                    let dt = seconds_between(estimate.updated_ms, now);
+                    for (axis, measurement) in estimate.axes.iter_mut().zip(measurements) {
+                        axis.update(measurement, dt, &self.smoothing);
+                    }
+                    estimate.updated_ms = now;
+                    estimate.smoothed_box(0.0)
+                }
+                None => {
+                    let estimate = Estimate {
+                        track: track.id(),
+                        updated_ms: now,
+                        axes: measurements
+                            .map(|measurement| Axis::new(measurement, &self.smoothing)),
+                    };
+                    let smoothed = estimate.smoothed_box(0.0);
+                    // Can't fail, there is at most one estimate for each track
+                    _ = self.estimates.push(estimate);
+                    smoothed
+                }
+            };
+            // Can't fail, there is at most one box for each track
+            _ = boxes.push(smoothed);
+        }
+        boxes
+    }
+
+    /// The smoothed box of `track` in the last frame it was detected in.
+    pub fn get(&self, track: TrackId) -> Option<SmoothedBox> {
+        self.estimate(track)
+            .map(|estimate| estimate.smoothed_box(0.0))
+    }
+
+    /// The smoothed box of `track` extrapolated to the current time, assuming it kept moving at
+    /// the same velocity since it was last detected.
+    pub fn predict(&self, track: TrackId) -> Option<SmoothedBox> {
+        let now = self.clock.now_ms();
+        self.estimate(track)
+            .map(|estimate| estimate.smoothed_box(seconds_between(estimate.updated_ms, now)))
+    }
+
+    /// The smoothed boxes of every tracked person, in the last frame each was detected in.
+    pub fn boxes(&self) -> impl Iterator<Item = SmoothedBox> + '_ {
+        self.estimates
+            .iter()
+            .map(|estimate| estimate.smoothed_box(0.0))
+    }
+
+    /// Forget every track and estimate.
+    pub fn reset(&mut self) {
+        self.tracker.reset();
+        self.estimates.clear();
+    }
+
+    fn estimate(&self, track: TrackId) -> Option<&Estimate> {
+        self.estimates
+            .iter()
+            .find(|estimate| estimate.track == track)
+    }
+}
+
+fn seconds_between(earlier_ms: u64, later_ms: u64) -> f32 {
+    later_ms.saturating_sub(earlier_ms) as f32 / 1_000.0
+}
@@ -0,0 +1,221 @@
+//! Following faces from one frame to the next.
+//!
+//! The sensor reports the faces of each frame independently. The [`Tracker`] associates them
+//! across frames, giving each person in view a [`TrackId`] that stays the same for as long as
+//! they are.
+
+use crate::{zone::Point, Face, MAX_DETECTIONS};
+
+/// Identifies a [`Track`]. IDs are assigned in increasing order and aren't reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackId(u32);
+
+impl From<TrackId> for u32 {
+    fn from(id: TrackId) -> u32 {
+        id.0
+    }
+}
+
+/// How the [`Tracker`] associates faces with tracks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TrackerConfig {
+    /// How far the center of a face can move between frames and still be the same person.
+    pub max_distance: u8,
+    /// How many frames in a row a person can go undetected before their track is lost.
+    pub max_missed_frames: u8,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        Self {
+            max_distance: 48,
+            max_missed_frames: 3,
+        }
+    }
+}
+
+/// A person followed across frames.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Track {
+    id: TrackId,
+    face: Face,
+    frames: u32,
+    missed: u8,
+}
+
+impl Track {
+    pub fn id(&self) -> TrackId {
+        self.id
+    }
+
+    /// The face in the last frame the person was detected in.
+    pub fn face(&self) -> &Face {
+        &self.face
+    }
+
+    /// The center of [`face`](Self::face).
+    pub fn center(&self) -> Point {
+        self.face.bounding_box().center()
+    }
+
+    /// The number of frames the person was detected in.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// The number of frames since the person was last detected.
+    pub fn missed_frames(&self) -> u8 {
+        self.missed
+    }
+
+    /// Whether the person was detected in the last frame.
+    pub fn is_visible(&self) -> bool {
+        self.missed == 0
+    }
+}
+
+/// Associates the faces of each frame with up to `TRACKS` tracks.
+///
+/// Each face is matched to the track whose last center is closest to its own, closest pairs
+/// first. Faces too far from every track start a new one. A track that isn't matched for more
+/// than [`max_missed_frames`](TrackerConfig::max_missed_frames) is lost, which keeps people who
+/// briefly look away or aren't detected in a frame on the same track.
+///
+/// Example:
+/// ```ignore
+/// let mut tracker = Tracker::<4>::new(TrackerConfig::default());
+///
+/// loop {
+///     let faces = person_sensor.get_detections().await?;
+///     for track in tracker.update(&faces) {
+///         info!("Track {} left after {} frames", u32::from(track.id()), track.frames());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tracker<const TRACKS: usize> {
+    config: TrackerConfig,
+    tracks: heapless::Vec<Track, TRACKS>,
+    next_id: u32,
+}
+
+impl<const TRACKS: usize> Tracker<TRACKS> {
+    pub fn new(config: TrackerConfig) -> Self {
+        Self {
+            config,
+            tracks: heapless::Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn config(&self) -> &TrackerConfig {
+        &self.config
+    }
+
+    /// All current tracks, including those not detected in the last frame.
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    pub fn get(&self, id: TrackId) -> Option<&Track> {
+        self.tracks.iter().find(|track| track.id == id)
+    }
+
+    /// Associate the faces of the next frame with the current tracks, returning the tracks that
+    /// were lost.
+    ///
+    /// Only the first [`MAX_DETECTIONS`] faces are used. When every track is taken, a new face
+    /// replaces the track missed for the longest, or is ignored if all of them are visible.
+    pub fn update(&mut self, faces: &[Face]) -> heapless::Vec<Track, TRACKS> {
+        let faces = &faces[..faces.len().min(MAX_DETECTIONS)];
+        let mut track_faces = [None; TRACKS];
+        let mut matched = [false; MAX_DETECTIONS];
+        let max_distance = u32::from(self.config.max_distance).pow(2);
+
+        // Match the closest pairs first
+        loop {
+            let mut closest: Option<(u32, usize, usize)> = None;
+            for (track_index, track) in self.tracks.iter().enumerate() {
+                if track_faces[track_index].is_some() {
+                    continue;
+                }
+                for (face_index, face) in faces.iter().enumerate() {
+                    if matched[face_index] {
+                        continue;
+                    }
+                    let distance = squared_distance(track.center(), face.bounding_box().center());
+                    if distance <= max_distance
+                        && closest.is_none_or(|(closest, _, _)| distance < closest)
+                    {
+                        closest = Some((distance, track_index, face_index));
+                    }
+                }
+            }
+            let Some((_, track_index, face_index)) = closest else {
+                break;
+            };
+            track_faces[track_index] = Some(face_index);
+            matched[face_index] = true;
+        }
+
+        let mut lost = heapless::Vec::new();
+        let mut index = 0;
+        for face_index in track_faces.into_iter().take(self.tracks.len()) {
+            let track = &mut self.tracks[index];
+            match face_index {
+                Some(face_index) => {
+                    track.face = faces[face_index].clone();
+                    track.frames = track.frames.saturating_add(1);
+                    track.missed = 0;
+                }
+                None => track.missed = track.missed.saturating_add(1),
+            }
+            if track.missed > self.config.max_missed_frames {
+                // Can't fail, there are at most as many lost tracks as tracks
+                _ = lost.push(self.tracks.remove(index));
+            } else {
+                index += 1;
+            }
+        }
+
+        for (face, _) in faces.iter().zip(matched).filter(|(_, matched)| !matched) {
+            if self.tracks.is_full() {
+                let Some(longest_missed) = self
+                    .tracks
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, track)| !track.is_visible())
+                    .max_by_key(|(_, track)| track.missed)
+                    .map(|(index, _)| index)
+                else {
+                    break;
+                };
+                _ = lost.push(self.tracks.remove(longest_missed));
+            }
+            _ = self.tracks.push(Track {
+                id: TrackId(self.next_id),
+                face: face.clone(),
+                frames: 1,
+                missed: 0,
+            });
+            self.next_id = self.next_id.wrapping_add(1);
+        }
+
+        lost
+    }
+
+    /// Forget every track, without reporting them as lost.
+    pub fn reset(&mut self) {
+        self.tracks.clear();
+    }
+}
+
+fn squared_distance(a: Point, b: Point) -> u32 {
+    let dx = u32::from(a.0.abs_diff(b.0));
+    let dy = u32::from(a.1.abs_diff(b.1));
+    dx * dx + dy * dy
+}
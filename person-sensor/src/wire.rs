@@ -0,0 +1,273 @@
+//! A compact, versioned encoding for streaming detections to a host, e.g. over UART or USB.
+//!
+//! Each frame is encoded as:
+//!
+//! | Field        | Size       | Description                                        |
+//! |--------------|------------|----------------------------------------------------|
+//! | version      | 1          | [`WIRE_VERSION`]                                   |
+//! | timestamp_ms | 4          | Little endian, set by the sender                   |
+//! | count        | 1          | Number of faces, up to [`MAX_DETECTIONS`]          |
+//! | faces        | 8 per face | See below                                          |
+//! | crc          | 2          | Little endian CRC-16/MCRF4XX of all previous bytes |
+//!
+//! Each face is encoded as its box confidence, left, top, right and bottom edges, recognition,
+//! recognition confidence, and flags. The recognition is the ID for
+//! identified faces, `0xfe` for faces that aren't the largest, and `0xff` for unknown faces. Bit 0
+//! of the flags is set if the face is facing the sensor.
+//!
+//! The frame is then [COBS](https://en.wikipedia.org/wiki/Consistent_Overhead_Byte_Stuffing)
+//! encoded and terminated with a `0x00` delimiter, so a receiver can resynchronize at the next
+//! delimiter after a corrupted or partial frame.
+
+use crc16::MCRF4XX;
+
+use crate::{Detections, Face, PersonID, Recognition, MAX_DETECTIONS};
+
+/// The version of the encoding produced by [`encode`].
+pub const WIRE_VERSION: u8 = 1;
+
+const HEADER_LEN: usize = 6;
+const FACE_LEN: usize = 8;
+const CRC_LEN: usize = 2;
+const MAX_FRAME_LEN: usize = HEADER_LEN + MAX_DETECTIONS * FACE_LEN + CRC_LEN;
+
+/// The largest possible encoded frame, including COBS overhead and the delimiter.
+pub const MAX_ENCODED_LEN: usize = MAX_FRAME_LEN + MAX_FRAME_LEN.div_ceil(254) + 1;
+
+const NOT_LARGEST_FACE: u8 = 0xfe;
+const UNKNOWN: u8 = 0xff;
+
+/// Errors encountered while encoding or decoding frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WireError {
+    /// The output buffer is too small for the encoded frame.
+    BufferTooSmall,
+    /// The COBS encoding is invalid, or the frame is too long.
+    InvalidEncoding,
+    /// The frame was encoded with an unsupported version.
+    UnsupportedVersion(u8),
+    /// The frame length does not match the number of faces it contains.
+    InvalidLength,
+    /// The frame was corrupted in transit.
+    ChecksumMismatch,
+    /// A face contains an invalid recognition.
+    InvalidFace,
+}
+
+/// A frame of detections, timestamped by the sender.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WireFrame {
+    pub timestamp_ms: u32,
+    pub faces: Detections,
+}
+
+/// Encode a frame into `buffer`, including the trailing delimiter. Returns the number of bytes
+/// written, which is at most [`MAX_ENCODED_LEN`].
+pub fn encode(frame: &WireFrame, buffer: &mut [u8]) -> Result<usize, WireError> {
+    let mut raw = [0u8; MAX_FRAME_LEN];
+    raw[0] = WIRE_VERSION;
+    raw[1..5].copy_from_slice(&frame.timestamp_ms.to_le_bytes());
+    raw[5] = frame.faces.len() as u8;
+
+    for (i, face) in frame.faces.iter().enumerate() {
+        let (recognition, confidence) = match face.recognition {
+            Recognition::Identified { id, confidence } => (u8::from(id), confidence),
+            Recognition::NotLargestFace => (NOT_LARGEST_FACE, 0),
+            Recognition::Unknown => (UNKNOWN, 0),
+        };
+        let offset = HEADER_LEN + i * FACE_LEN;
+        raw[offset..offset + FACE_LEN].copy_from_slice(&[
+            face.box_confidence,
+            face.box_left,
+            face.box_top,
+            face.box_right,
+            face.box_bottom,
+            recognition,
+            confidence,
+            face.is_facing as u8,
+        ]);
+    }
+
+    let len = HEADER_LEN + frame.faces.len() * FACE_LEN;
+    let checksum = crc16::State::<MCRF4XX>::calculate(&raw[..len]);
+    raw[len..len + CRC_LEN].copy_from_slice(&checksum.to_le_bytes());
+
+    let encoded_len = cobs_encode(&raw[..len + CRC_LEN], buffer)?;
+    *buffer
+        .get_mut(encoded_len)
+        .ok_or(WireError::BufferTooSmall)? = 0;
+    Ok(encoded_len + 1)
+}
+
+/// Decode a single frame. The trailing delimiter is optional.
+pub fn decode(encoded: &[u8]) -> Result<WireFrame, WireError> {
+    let encoded = encoded.strip_suffix(&[0]).unwrap_or(encoded);
+    let mut raw = [0u8; MAX_FRAME_LEN];
+    let len = cobs_decode(encoded, &mut raw)?;
+    let raw = &raw[..len];
+
+    let version = *raw.first().ok_or(WireError::InvalidLength)?;
+    if version != WIRE_VERSION {
+        return Err(WireError::UnsupportedVersion(version));
+    }
+    if len < HEADER_LEN + CRC_LEN {
+        return Err(WireError::InvalidLength);
+    }
+    let count = usize::from(raw[5]);
+    if count > MAX_DETECTIONS || len != HEADER_LEN + count * FACE_LEN + CRC_LEN {
+        return Err(WireError::InvalidLength);
+    }
+
+    let (body, checksum) = raw.split_at(len - CRC_LEN);
+    if crc16::State::<MCRF4XX>::calculate(body) != u16::from_le_bytes([checksum[0], checksum[1]]) {
+        return Err(WireError::ChecksumMismatch);
+    }
+
+    let mut faces = Detections::new();
+    for record in body[HEADER_LEN..].chunks_exact(FACE_LEN) {
+        let recognition = match record[5] {
+            NOT_LARGEST_FACE => Recognition::NotLargestFace,
+            UNKNOWN => Recognition::Unknown,
+            id => PersonID::new(id)
+                .ok()
+                .and_then(|id| Recognition::identified(id, record[6]).ok())
+                .ok_or(WireError::InvalidFace)?,
+        };
+        let face = Face {
+            box_confidence: record[0],
+            box_left: record[1],
+            box_top: record[2],
+            box_right: record[3],
+            box_bottom: record[4],
+            recognition,
+            is_facing: record[7] & 1 != 0,
+        };
+        // The count was checked above
+        _ = faces.push(face);
+    }
+
+    Ok(WireFrame {
+        timestamp_ms: u32::from_le_bytes([body[1], body[2], body[3], body[4]]),
+        faces,
+    })
+}
+
+/// Splits a byte stream into frames at each delimiter, and decodes them.
+///
+/// Bytes received before the first delimiter are discarded, as they may be the tail of a frame
+/// that was only partially received.
+///
+/// Example:
+/// ```ignore
+/// let mut decoder = StreamDecoder::new();
+///
+/// for byte in uart.bytes() {
+///     if let Some(result) = decoder.push(byte) {
+///         // Handle the frame or error
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamDecoder {
+    buffer: heapless::Vec<u8, MAX_ENCODED_LEN>,
+    synchronized: bool,
+    overflowed: bool,
+}
+
+impl Default for StreamDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamDecoder {
+    pub const fn new() -> Self {
+        Self {
+            buffer: heapless::Vec::new(),
+            synchronized: false,
+            overflowed: false,
+        }
+    }
+
+    /// Add a byte from the stream, returning the decoded frame if it completes one.
+    pub fn push(&mut self, byte: u8) -> Option<Result<WireFrame, WireError>> {
+        if byte != 0 {
+            if self.buffer.push(byte).is_err() {
+                self.overflowed = true;
+            }
+            return None;
+        }
+
+        let result = match (self.synchronized, self.overflowed) {
+            (false, _) => None,
+            // Consecutive delimiters
+            (true, _) if self.buffer.is_empty() => None,
+            (true, true) => Some(Err(WireError::InvalidEncoding)),
+            (true, false) => Some(decode(&self.buffer)),
+        };
+        self.buffer.clear();
+        self.synchronized = true;
+        self.overflowed = false;
+        result
+    }
+}
+
+fn cobs_encode(input: &[u8], output: &mut [u8]) -> Result<usize, WireError> {
+    let mut code_index = 0;
+    let mut write_index = 1;
+    let mut code = 1u8;
+
+    for &byte in input {
+        if byte != 0 {
+            *output
+                .get_mut(write_index)
+                .ok_or(WireError::BufferTooSmall)? = byte;
+            write_index += 1;
+            code += 1;
+        }
+        if byte == 0 || code == 0xff {
+            *output
+                .get_mut(code_index)
+                .ok_or(WireError::BufferTooSmall)? = code;
+            code_index = write_index;
+            write_index += 1;
+            code = 1;
+        }
+    }
+    *output
+        .get_mut(code_index)
+        .ok_or(WireError::BufferTooSmall)? = code;
+    Ok(write_index)
+}
+
+fn cobs_decode(input: &[u8], output: &mut [u8]) -> Result<usize, WireError> {
+    let mut read_index = 0;
+    let mut write_index = 0;
+
+    while read_index < input.len() {
+        let code = input[read_index];
+        if code == 0 || read_index + usize::from(code) > input.len() {
+            return Err(WireError::InvalidEncoding);
+        }
+        read_index += 1;
+
+        for _ in 1..code {
+            *output
+                .get_mut(write_index)
+                .ok_or(WireError::InvalidEncoding)? = input[read_index];
+            read_index += 1;
+            write_index += 1;
+        }
+
+        if code != 0xff && read_index < input.len() {
+            *output
+                .get_mut(write_index)
+                .ok_or(WireError::InvalidEncoding)? = 0;
+            write_index += 1;
+        }
+    }
+    Ok(write_index)
+}
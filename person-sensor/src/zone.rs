@@ -0,0 +1,376 @@
+//! Regions of interest in the sensor's view, and how many people are in each.
+//!
+//! A [`Zone`] is a rectangle or polygon in sensor coordinates. A face is in a zone when enough of
+//! its bounding box overlaps it. The [`ZoneMonitor`] counts the faces in each zone on every frame,
+//! and reports when people enter or leave.
+//!
+//! Example:
+//! ```ignore
+//! let mut monitor = ZoneMonitor::<2>::new();
+//! let desk = BoundingBox { left: 0, top: 100, right: 127, bottom: 255 };
+//! let desk = monitor.add(Zone::rect(desk, 50))?;
+//! let door = [(180, 0), (255, 0), (255, 255), (150, 255)];
+//! let door = monitor.add(Zone::polygon(&door, 30)?)?;
+//!
+//! loop {
+//!     let faces = person_sensor.get_detections().await?;
+//!     for event in monitor.update(&faces) {
+//!         match event {
+//!             ZoneEvent::Entered { zone, .. } if zone == door => chime(),
+//!             _ => {}
+//!         }
+//!     }
+//!     display.show_desk_occupancy(monitor.occupancy(desk));
+//! }
+//! ```
+
+use crate::{BoundingBox, Face};
+
+/// The maximum number of vertices in a [`Polygon`].
+pub const MAX_POLYGON_VERTICES: usize = 8;
+
+/// A point in sensor coordinates, as `(x, y)`.
+pub type Point = (u8, u8);
+
+/// The reasons a zone can't be created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ZoneError {
+    /// A polygon needs at least 3 vertices.
+    TooFewVertices,
+    /// A polygon can have at most [`MAX_POLYGON_VERTICES`] vertices.
+    TooManyVertices,
+    /// The monitor or filter already holds as many zones as it can.
+    Full,
+}
+
+impl core::fmt::Display for ZoneError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TooFewVertices => f.write_str("a polygon needs at least 3 vertices"),
+            Self::TooManyVertices => write!(
+                f,
+                "a polygon can have at most {MAX_POLYGON_VERTICES} vertices"
+            ),
+            Self::Full => f.write_str("no more zones can be added"),
+        }
+    }
+}
+
+/// A simple polygon, with its vertices in order. It doesn't need to be convex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        try_from = "heapless::Vec<Point, MAX_POLYGON_VERTICES>",
+        into = "heapless::Vec<Point, MAX_POLYGON_VERTICES>"
+    )
+)]
+pub struct Polygon {
+    vertices: heapless::Vec<Point, MAX_POLYGON_VERTICES>,
+}
+
+impl Polygon {
+    pub fn new(vertices: &[Point]) -> Result<Self, ZoneError> {
+        if vertices.len() < 3 {
+            return Err(ZoneError::TooFewVertices);
+        }
+        let vertices =
+            heapless::Vec::from_slice(vertices).map_err(|_| ZoneError::TooManyVertices)?;
+        Ok(Self { vertices })
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    /// Whether `point` is inside the polygon. Points on the edges may be either inside or out.
+    pub fn contains(&self, point: Point) -> bool {
+        let (x, y) = (f32::from(point.0), f32::from(point.1));
+        let Some(&last) = self.vertices.last() else {
+            return false;
+        };
+        let mut inside = false;
+        let mut previous = last;
+        for &vertex in &self.vertices {
+            let (x1, y1) = (f32::from(vertex.0), f32::from(vertex.1));
+            let (x2, y2) = (f32::from(previous.0), f32::from(previous.1));
+            if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+                inside = !inside;
+            }
+            previous = vertex;
+        }
+        inside
+    }
+
+    /// The area of the part of `bounding_box` inside the polygon.
+    fn intersection_area(&self, bounding_box: &BoundingBox) -> f32 {
+        // Clip the polygon against each edge of the box in turn. The box is convex, so this works
+        // for any simple polygon.
+        let mut clipped: heapless::Vec<(f32, f32), CLIPPED_VERTICES> = self
+            .vertices
+            .iter()
+            .map(|&(x, y)| (f32::from(x), f32::from(y)))
+            .collect();
+        let (left, top) = (f32::from(bounding_box.left), f32::from(bounding_box.top));
+        let (right, bottom) = (
+            f32::from(bounding_box.right),
+            f32::from(bounding_box.bottom),
+        );
+        clipped = clip(&clipped, |(x, _)| x - left, |a, b| at_x(a, b, left));
+        clipped = clip(&clipped, |(x, _)| right - x, |a, b| at_x(a, b, right));
+        clipped = clip(&clipped, |(_, y)| y - top, |a, b| at_y(a, b, top));
+        clipped = clip(&clipped, |(_, y)| bottom - y, |a, b| at_y(a, b, bottom));
+        shoelace_area(&clipped)
+    }
+}
+
+impl TryFrom<heapless::Vec<Point, MAX_POLYGON_VERTICES>> for Polygon {
+    type Error = ZoneError;
+    fn try_from(vertices: heapless::Vec<Point, MAX_POLYGON_VERTICES>) -> Result<Self, Self::Error> {
+        Self::new(&vertices)
+    }
+}
+
+impl From<Polygon> for heapless::Vec<Point, MAX_POLYGON_VERTICES> {
+    fn from(polygon: Polygon) -> Self {
+        polygon.vertices
+    }
+}
+
+/// Each clipping edge can add at most one vertex.
+const CLIPPED_VERTICES: usize = MAX_POLYGON_VERTICES + 4;
+
+/// Keeps the part of `polygon` where `distance` is not negative.
+fn clip(
+    polygon: &[(f32, f32)],
+    distance: impl Fn((f32, f32)) -> f32,
+    intersect: impl Fn((f32, f32), (f32, f32)) -> (f32, f32),
+) -> heapless::Vec<(f32, f32), CLIPPED_VERTICES> {
+    let mut output = heapless::Vec::new();
+    let Some(&last) = polygon.last() else {
+        return output;
+    };
+    let mut previous = last;
+    for &current in polygon {
+        let current_inside = distance(current) >= 0.0;
+        let previous_inside = distance(previous) >= 0.0;
+        if current_inside != previous_inside {
+            // Can't fail, see CLIPPED_VERTICES
+            _ = output.push(intersect(previous, current));
+        }
+        if current_inside {
+            _ = output.push(current);
+        }
+        previous = current;
+    }
+    output
+}
+
+fn at_x(a: (f32, f32), b: (f32, f32), x: f32) -> (f32, f32) {
+    (x, a.1 + (b.1 - a.1) * (x - a.0) / (b.0 - a.0))
+}
+
+fn at_y(a: (f32, f32), b: (f32, f32), y: f32) -> (f32, f32) {
+    (a.0 + (b.0 - a.0) * (y - a.1) / (b.1 - a.1), y)
+}
+
+fn shoelace_area(polygon: &[(f32, f32)]) -> f32 {
+    let Some(&last) = polygon.last() else {
+        return 0.0;
+    };
+    let mut previous = last;
+    let mut twice_area = 0.0;
+    for &current in polygon {
+        twice_area += previous.0 * current.1 - current.0 * previous.1;
+        previous = current;
+    }
+    let area = twice_area / 2.0;
+    if area < 0.0 {
+        -area
+    } else {
+        area
+    }
+}
+
+/// The shape of a [`Zone`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Region {
+    Rect(BoundingBox),
+    Polygon(Polygon),
+}
+
+impl Region {
+    /// The percentage of `bounding_box` inside the region, from 0 to 100.
+    ///
+    /// A box without any area is either entirely inside or outside, depending on its center.
+    pub fn overlap_percent(&self, bounding_box: &BoundingBox) -> u8 {
+        let area = bounding_box.area();
+        if area == 0 {
+            return if self.contains(bounding_box.center()) {
+                100
+            } else {
+                0
+            };
+        }
+
+        match self {
+            Self::Rect(rect) => {
+                let intersection = BoundingBox {
+                    left: rect.left.max(bounding_box.left),
+                    top: rect.top.max(bounding_box.top),
+                    right: rect.right.min(bounding_box.right),
+                    bottom: rect.bottom.min(bounding_box.bottom),
+                };
+                (u32::from(intersection.area()) * 100 / u32::from(area)) as u8
+            }
+            Self::Polygon(polygon) => {
+                let percent = polygon.intersection_area(bounding_box) * 100.0 / f32::from(area);
+                // Rounding errors could otherwise go slightly beyond 100
+                (percent as u8).min(100)
+            }
+        }
+    }
+
+    /// Whether `point` is inside the region.
+    pub fn contains(&self, point: Point) -> bool {
+        match self {
+            Self::Rect(rect) => {
+                (rect.left..=rect.right).contains(&point.0)
+                    && (rect.top..=rect.bottom).contains(&point.1)
+            }
+            Self::Polygon(polygon) => polygon.contains(point),
+        }
+    }
+}
+
+/// A region of interest, and how much of a face must overlap it for the face to be inside.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Zone {
+    pub region: Region,
+    /// The percentage of a face's bounding box that must be inside the region, from 0 to 100. At
+    /// 0, any overlap is enough.
+    pub min_overlap_percent: u8,
+}
+
+impl Zone {
+    pub fn rect(rect: BoundingBox, min_overlap_percent: u8) -> Self {
+        Self {
+            region: Region::Rect(rect),
+            min_overlap_percent,
+        }
+    }
+
+    pub fn polygon(vertices: &[Point], min_overlap_percent: u8) -> Result<Self, ZoneError> {
+        Ok(Self {
+            region: Region::Polygon(Polygon::new(vertices)?),
+            min_overlap_percent,
+        })
+    }
+
+    /// Whether `face` is inside the zone.
+    pub fn contains(&self, face: &Face) -> bool {
+        let overlap = self.region.overlap_percent(&face.bounding_box());
+        overlap > 0 && overlap >= self.min_overlap_percent
+    }
+
+    /// The number of `faces` inside the zone.
+    pub fn count(&self, faces: &[Face]) -> u8 {
+        faces.iter().filter(|face| self.contains(face)).count() as u8
+    }
+}
+
+/// A change in the number of people in a zone, reported by the [`ZoneMonitor`].
+///
+/// Faces aren't followed between frames, so a person moving from one zone to another in a single
+/// frame is reported as an exit and an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ZoneEvent {
+    /// `people` entered the zone, which now holds `occupancy` people.
+    Entered {
+        zone: usize,
+        people: u8,
+        occupancy: u8,
+    },
+    /// `people` left the zone, which now holds `occupancy` people.
+    Exited {
+        zone: usize,
+        people: u8,
+        occupancy: u8,
+    },
+}
+
+/// Counts the faces in up to `ZONES` zones on every frame.
+#[derive(Debug, Clone)]
+pub struct ZoneMonitor<const ZONES: usize> {
+    zones: heapless::Vec<(Zone, u8), ZONES>,
+}
+
+impl<const ZONES: usize> Default for ZoneMonitor<ZONES> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const ZONES: usize> ZoneMonitor<ZONES> {
+    pub fn new() -> Self {
+        Self {
+            zones: heapless::Vec::new(),
+        }
+    }
+
+    /// Add a zone, returning its index. Zones start out empty.
+    pub fn add(&mut self, zone: Zone) -> Result<usize, ZoneError> {
+        self.zones.push((zone, 0)).map_err(|_| ZoneError::Full)?;
+        Ok(self.zones.len() - 1)
+    }
+
+    pub fn zone(&self, zone: usize) -> Option<&Zone> {
+        self.zones.get(zone).map(|(zone, _)| zone)
+    }
+
+    /// The number of people in `zone` in the last frame. Unknown zones are empty.
+    pub fn occupancy(&self, zone: usize) -> u8 {
+        self.zones.get(zone).map_or(0, |(_, occupancy)| *occupancy)
+    }
+
+    /// Count the faces of the next frame in each zone, returning the zones whose occupancy
+    /// changed.
+    pub fn update(&mut self, faces: &[Face]) -> heapless::Vec<ZoneEvent, ZONES> {
+        let mut events = heapless::Vec::new();
+        for (index, (zone, occupancy)) in self.zones.iter_mut().enumerate() {
+            let previous = *occupancy;
+            *occupancy = zone.count(faces);
+            let event = match (*occupancy).cmp(&previous) {
+                core::cmp::Ordering::Greater => ZoneEvent::Entered {
+                    zone: index,
+                    people: *occupancy - previous,
+                    occupancy: *occupancy,
+                },
+                core::cmp::Ordering::Less => ZoneEvent::Exited {
+                    zone: index,
+                    people: previous - *occupancy,
+                    occupancy: *occupancy,
+                },
+                core::cmp::Ordering::Equal => continue,
+            };
+            // Can't fail, there is at most one event per zone
+            _ = events.push(event);
+        }
+        events
+    }
+
+    /// Forget the occupancy of every zone, without reporting anyone as leaving.
+    pub fn reset(&mut self) {
+        for (_, occupancy) in &mut self.zones {
+            *occupancy = 0;
+        }
+    }
+}
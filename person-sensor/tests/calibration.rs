@@ -0,0 +1,163 @@
+mod common;
+use common::{face, MockPersonSensorBus, NoopDelay, NO_FACES, ONE_FACE, TWO_FACES};
+use person_sensor::{
+    calibration::{CalibrationConfig, CalibrationError},
+    encode_frame, Face, PersonID, PersonSensorBuilder, Recognition,
+};
+
+fn label_writes(i2c: &MockPersonSensorBus) -> Vec<u8> {
+    i2c.writes()
+        .iter()
+        .filter(|w| w[0] == 0x04)
+        .map(|w| w[1])
+        .collect()
+}
+
+#[tokio::test]
+async fn calibrates_and_verifies() {
+    let mut i2c = MockPersonSensorBus::new(0, ONE_FACE);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(&mut i2c, true)
+        .build()
+        .await
+        .unwrap();
+
+    let id = PersonID::new(0).unwrap();
+    let result = person_sensor
+        .calibrate(id, &CalibrationConfig::default(), &mut NoopDelay)
+        .await;
+    assert_eq!(result, Ok(()));
+    assert_eq!(label_writes(&i2c), [0]);
+}
+
+#[tokio::test]
+async fn calibrates_in_standby() {
+    let mut i2c = MockPersonSensorBus::new(1, ONE_FACE);
+    let mut person_sensor = PersonSensorBuilder::new_standby(&mut i2c, true)
+        .build()
+        .await
+        .unwrap();
+
+    let id = PersonID::new(0).unwrap();
+    let result = person_sensor
+        .calibrate(id, &CalibrationConfig::default(), &mut NoopDelay)
+        .await;
+    assert_eq!(result, Ok(()));
+
+    // One capture before labeling, then one per verification frame
+    let captures = i2c.writes().iter().filter(|w| w[0] == 0x03).count();
+    assert_eq!(captures, 4);
+}
+
+#[tokio::test]
+async fn no_face() {
+    let mut i2c = MockPersonSensorBus::new(0, NO_FACES);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(&mut i2c, true)
+        .build()
+        .await
+        .unwrap();
+
+    let id = PersonID::new(0).unwrap();
+    let result = person_sensor
+        .calibrate(id, &CalibrationConfig::default(), &mut NoopDelay)
+        .await;
+    assert_eq!(result, Err(CalibrationError::NoFace));
+    assert!(label_writes(&i2c).is_empty());
+}
+
+#[tokio::test]
+async fn low_confidence_face_is_ignored() {
+    let low_confidence = encode_frame(&[Face {
+        box_confidence: 32,
+        ..face()
+    }]);
+    let mut i2c = MockPersonSensorBus::new(0, low_confidence);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(&mut i2c, true)
+        .build()
+        .await
+        .unwrap();
+
+    let id = PersonID::new(0).unwrap();
+    let result = person_sensor
+        .calibrate(id, &CalibrationConfig::default(), &mut NoopDelay)
+        .await;
+    assert_eq!(result, Err(CalibrationError::NoFace));
+}
+
+#[tokio::test]
+async fn multiple_faces() {
+    let mut i2c = MockPersonSensorBus::new(0, TWO_FACES);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(&mut i2c, true)
+        .build()
+        .await
+        .unwrap();
+
+    let id = PersonID::new(0).unwrap();
+    let result = person_sensor
+        .calibrate(id, &CalibrationConfig::default(), &mut NoopDelay)
+        .await;
+    assert_eq!(result, Err(CalibrationError::MultipleFaces));
+    assert!(label_writes(&i2c).is_empty());
+}
+
+#[tokio::test]
+async fn not_facing() {
+    let not_facing = encode_frame(&[Face {
+        is_facing: false,
+        ..face()
+    }]);
+    let mut i2c = MockPersonSensorBus::new(0, not_facing);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(&mut i2c, true)
+        .build()
+        .await
+        .unwrap();
+
+    let id = PersonID::new(0).unwrap();
+    let result = person_sensor
+        .calibrate(id, &CalibrationConfig::default(), &mut NoopDelay)
+        .await;
+    assert_eq!(result, Err(CalibrationError::NotFacing));
+    assert!(label_writes(&i2c).is_empty());
+}
+
+#[tokio::test]
+async fn verification_failed() {
+    // The face keeps being recognized as person 0, never as the requested person 3
+    let mut i2c = MockPersonSensorBus::new(0, ONE_FACE);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(&mut i2c, true)
+        .build()
+        .await
+        .unwrap();
+
+    let id = PersonID::new(3).unwrap();
+    let result = person_sensor
+        .calibrate(id, &CalibrationConfig::default(), &mut NoopDelay)
+        .await;
+    assert_eq!(result, Err(CalibrationError::VerificationFailed));
+    assert_eq!(label_writes(&i2c), [3]);
+}
+
+#[tokio::test]
+async fn verification_requires_confident_matches() {
+    let unsure = encode_frame(&[Face {
+        recognition: Recognition::Identified {
+            id: PersonID::new(0).unwrap(),
+            confidence: 32,
+        },
+        ..face()
+    }]);
+    let payloads = [ONE_FACE, unsure, ONE_FACE, unsure, ONE_FACE];
+    let mut i2c = MockPersonSensorBus::with_payloads(0, &payloads);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(&mut i2c, true)
+        .build()
+        .await
+        .unwrap();
+
+    let config = CalibrationConfig {
+        verification_frames: 3,
+        required_matches: 2,
+        ..CalibrationConfig::default()
+    };
+    let id = PersonID::new(0).unwrap();
+    let result = person_sensor.calibrate(id, &config, &mut NoopDelay).await;
+    assert_eq!(result, Err(CalibrationError::VerificationFailed));
+}
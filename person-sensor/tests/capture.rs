@@ -0,0 +1,217 @@
+mod common;
+use common::{
+    MockClock, MockDelay, MockInterrupt, MockPersonSensorBus, BAD_CHECKSUM, NO_FACES, ONE_FACE,
+};
+use person_sensor::{CaptureWait, PersonSensorBuilder};
+
+#[tokio::test]
+async fn delay_before_reading() {
+    let i2c = MockPersonSensorBus::new(1, ONE_FACE);
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+
+    let mut person_sensor = PersonSensorBuilder::new_standby(i2c, false)
+        .with_capture_wait(CaptureWait::Delay { ms: 150 })
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor.capture_once(&mut delay).await.unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(clock.now_ms(), 150);
+}
+
+#[tokio::test]
+async fn until_changed_returns_new_frame() {
+    // The first read is the frame available before the capture was requested
+    let payloads = [NO_FACES, NO_FACES, NO_FACES, ONE_FACE];
+    let i2c = MockPersonSensorBus::with_payloads(1, &payloads);
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+
+    let mut person_sensor = PersonSensorBuilder::new_standby(i2c, false)
+        .with_capture_wait(CaptureWait::UntilChanged {
+            interval_ms: 50,
+            timeout_ms: 1_000,
+        })
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor.capture_once(&mut delay).await.unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(clock.now_ms(), 150);
+}
+
+#[tokio::test]
+async fn until_changed_times_out_on_unchanged_frame() {
+    let i2c = MockPersonSensorBus::new(1, ONE_FACE);
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+
+    let mut person_sensor = PersonSensorBuilder::new_standby(i2c, false)
+        .with_capture_wait(CaptureWait::UntilChanged {
+            interval_ms: 50,
+            timeout_ms: 200,
+        })
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor.capture_once(&mut delay).await.unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(clock.now_ms(), 200);
+}
+
+#[tokio::test]
+async fn until_changed_with_zero_interval_times_out() {
+    let i2c = MockPersonSensorBus::new(1, ONE_FACE);
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+
+    let mut person_sensor = PersonSensorBuilder::new_standby(i2c, false)
+        .with_capture_wait(CaptureWait::UntilChanged {
+            interval_ms: 0,
+            timeout_ms: 5,
+        })
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor.capture_once(&mut delay).await.unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(clock.now_ms(), 5);
+}
+
+#[tokio::test]
+async fn until_changed_with_long_intervals_times_out() {
+    let i2c = MockPersonSensorBus::new(1, ONE_FACE);
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+
+    let mut person_sensor = PersonSensorBuilder::new_standby(i2c, false)
+        .with_capture_wait(CaptureWait::UntilChanged {
+            interval_ms: 3_000_000_000,
+            timeout_ms: u32::MAX,
+        })
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor.capture_once(&mut delay).await.unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(clock.now_ms(), 6_000_000_000);
+}
+
+#[tokio::test]
+async fn until_changed_skips_torn_frame() {
+    let payloads = [NO_FACES, BAD_CHECKSUM, ONE_FACE];
+    let i2c = MockPersonSensorBus::with_payloads(1, &payloads);
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+
+    let mut person_sensor = PersonSensorBuilder::new_standby(i2c, false)
+        .with_capture_wait(CaptureWait::UntilChanged {
+            interval_ms: 50,
+            timeout_ms: 1_000,
+        })
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor.capture_once(&mut delay).await.unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(clock.now_ms(), 100);
+}
+
+#[tokio::test]
+async fn until_changed_reports_checksum_mismatch_at_timeout() {
+    let payloads = [NO_FACES, BAD_CHECKSUM];
+    let i2c = MockPersonSensorBus::with_payloads(1, &payloads);
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+
+    let mut person_sensor = PersonSensorBuilder::new_standby(i2c, false)
+        .with_capture_wait(CaptureWait::UntilChanged {
+            interval_ms: 50,
+            timeout_ms: 200,
+        })
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        person_sensor.capture_once(&mut delay).await,
+        Err(person_sensor::ReadError::ChecksumMismatch)
+    );
+    assert_eq!(clock.now_ms(), 200);
+}
+
+#[tokio::test]
+async fn interrupt_returns_on_the_next_rising_edge() {
+    let i2c = MockPersonSensorBus::new(1, ONE_FACE);
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+
+    // The pin is still high from the previous frame, and rises again once the new one is ready
+    let mut person_sensor = PersonSensorBuilder::new_standby(i2c, false)
+        .with_interrupt(MockInterrupt {
+            high: true,
+            rising_edge: true,
+        })
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor
+        .capture_once_with_interrupt(&mut delay, 500)
+        .await
+        .unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(clock.now_ms(), 0);
+}
+
+#[tokio::test]
+async fn interrupt_already_high_is_not_a_new_frame() {
+    let i2c = MockPersonSensorBus::new(1, ONE_FACE);
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+
+    let mut person_sensor = PersonSensorBuilder::new_standby(i2c, false)
+        .with_interrupt(MockInterrupt {
+            high: true,
+            rising_edge: false,
+        })
+        .build()
+        .await
+        .unwrap();
+
+    person_sensor
+        .capture_once_with_interrupt(&mut delay, 500)
+        .await
+        .unwrap();
+    assert_eq!(clock.now_ms(), 500);
+}
+
+#[tokio::test]
+async fn interrupt_times_out_without_face() {
+    let i2c = MockPersonSensorBus::new(1, NO_FACES);
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+
+    let mut person_sensor = PersonSensorBuilder::new_standby(i2c, false)
+        .with_interrupt(MockInterrupt {
+            high: false,
+            rising_edge: false,
+        })
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor
+        .capture_once_with_interrupt(&mut delay, 500)
+        .await
+        .unwrap();
+    assert!(detections.is_empty());
+    assert_eq!(clock.now_ms(), 500);
+}
@@ -0,0 +1,107 @@
+mod common;
+use common::{MockPersonSensorBus, NoopDelay, NO_FACES, ONE_FACE, TWO_FACES};
+use person_sensor::{
+    command::{Command, CommandExecutor},
+    power::ManagedSensor,
+    PersonID, PersonSensorBuilder, PersonSensorMode,
+};
+
+/// The writes made after the builder configured the sensor.
+fn command_writes(executor: CommandExecutor<MockPersonSensorBus, ()>) -> Vec<Vec<u8>> {
+    let (i2c, ()) = match executor.release() {
+        ManagedSensor::Standby(sensor) => sensor.release(),
+        ManagedSensor::Continuous(sensor) => sensor.release(),
+    };
+    i2c.writes()[2..].to_vec()
+}
+
+#[tokio::test]
+async fn register_writes() {
+    let i2c = MockPersonSensorBus::new(1, NO_FACES);
+    let sensor = PersonSensorBuilder::new_continuous(i2c, true)
+        .build()
+        .await
+        .unwrap();
+    let mut executor = CommandExecutor::new(sensor);
+
+    for command in [
+        Command::EnableIdModel(false),
+        Command::LabelNextId(PersonID::new(5).unwrap()),
+        Command::SetPersistIds(false),
+        Command::EraseIds,
+        Command::SetIndicator(false),
+        Command::SetMode(PersonSensorMode::Standby),
+    ] {
+        let result = executor.execute(command, &mut NoopDelay).await;
+        assert_eq!(result, Ok(None));
+    }
+    assert_eq!(executor.mode(), PersonSensorMode::Standby);
+    assert_eq!(
+        command_writes(executor),
+        [
+            [0x02, 0x00],
+            [0x04, 0x05],
+            [0x05, 0x00],
+            [0x06, 0x00],
+            [0x07, 0x00],
+            [0x01, 0x00],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn capture_once_in_standby() {
+    let i2c = MockPersonSensorBus::with_payloads(0, &[ONE_FACE, TWO_FACES]);
+    let sensor = PersonSensorBuilder::new_standby(i2c, true)
+        .build()
+        .await
+        .unwrap();
+    let mut executor = CommandExecutor::from_standby(sensor);
+
+    let detections = executor
+        .execute(Command::CaptureOnce, &mut NoopDelay)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(command_writes(executor), [[0x03, 0x00]]);
+}
+
+#[tokio::test]
+async fn capture_once_in_continuous_mode_reads_latest() {
+    let i2c = MockPersonSensorBus::new(1, TWO_FACES);
+    let sensor = PersonSensorBuilder::new_continuous(i2c, true)
+        .build()
+        .await
+        .unwrap();
+    let mut executor = CommandExecutor::new(sensor);
+
+    let detections = executor
+        .execute(Command::CaptureOnce, &mut NoopDelay)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(detections.len(), 2);
+    assert!(command_writes(executor).is_empty());
+}
+
+#[tokio::test]
+async fn mode_changes_are_tracked() {
+    let i2c = MockPersonSensorBus::new(1, NO_FACES);
+    let sensor = PersonSensorBuilder::new_standby(i2c, true)
+        .build()
+        .await
+        .unwrap();
+    let mut executor = CommandExecutor::from_standby(sensor);
+    assert_eq!(executor.mode(), PersonSensorMode::Standby);
+
+    executor
+        .execute(
+            Command::SetMode(PersonSensorMode::Continuous),
+            &mut NoopDelay,
+        )
+        .await
+        .unwrap();
+    assert_eq!(executor.mode(), PersonSensorMode::Continuous);
+    assert!(matches!(executor.release(), ManagedSensor::Continuous(_)));
+}
@@ -1,18 +1,117 @@
+#![allow(dead_code)]
+
+use core::cell::Cell;
+use core::future::pending;
+
+use embedded_hal::digital::ErrorType as DigitalErrorType;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::digital::Wait;
 use embedded_hal_async::i2c::{self, ErrorKind, ErrorType, I2c, Operation, SevenBitAddress};
+use person_sensor::{Face, PersonID, Recognition};
+
+/// Person 0, looking at the sensor, as captured in [`ONE_FACE`].
+pub fn face() -> Face {
+    Face {
+        box_confidence: 99,
+        box_left: 124,
+        box_top: 128,
+        box_right: 149,
+        box_bottom: 170,
+        recognition: Recognition::Identified {
+            id: PersonID::new(0).unwrap(),
+            confidence: 67,
+        },
+        is_facing: true,
+    }
+}
+
+// Frames captured from a sensor
+pub const NO_FACES: [u8; 39] = [
+    0x00, 0x00, 0x21, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x25, 0x37,
+];
+/// A single [`face`].
+pub const ONE_FACE: [u8; 39] = [
+    0x00, 0x00, 0x21, 0x00, 0x01, 0x63, 0x7c, 0x80, 0x95, 0xaa, 0x43, 0x00, 0x01, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x15, 0x8b,
+];
+pub const TWO_FACES: [u8; 39] = [
+    0x00, 0x00, 0x21, 0x00, 0x02, 0x63, 0x3e, 0x5e, 0x62, 0x9e, 0x4e, 0x00, 0x01, 0x5e, 0x79, 0x67,
+    0x8e, 0x88, 0x38, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0xb9, 0xf9,
+];
+/// [`TWO_FACES`] with a corrupted byte.
+pub const BAD_CHECKSUM: [u8; 39] = [
+    0x00, 0x00, 0x21, 0x00, 0x02, 0x63, 0x3e, 0x5e, 0x62, 0x9e, 0x4e, 0x00, 0x01, 0x5e, 0x79, 0x67,
+    0x8e, 0x88, 0x38, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x01, 0xb9, 0xf9,
+];
+
+/// Builds a valid sensor payload from raw 8 byte face records, including ones that can't be
+/// represented as a [`Face`]:
+/// `[box_confidence, left, top, right, bottom, id_confidence, id, is_facing]`
+pub fn payload(faces: &[[u8; 8]]) -> [u8; 39] {
+    let mut payload = [0u8; 39];
+    payload[2] = 0x21;
+    payload[4] = faces.len() as u8;
+    for (i, face) in faces.iter().enumerate() {
+        payload[5 + i * 8..13 + i * 8].copy_from_slice(face);
+    }
+    let checksum = crc16::State::<crc16::MCRF4XX>::calculate(&payload[..37]);
+    payload[37..].copy_from_slice(&checksum.to_le_bytes());
+    payload
+}
 
 #[derive(Debug)]
-pub struct MockPersonSensorBus<'a> {
+pub struct MockPersonSensorBus {
     mode: u8,
-    payload: &'a [u8; 39],
+    payloads: Vec<[u8; 39]>,
+    next_payload: usize,
+    probe_payload: [u8; 39],
+    writes: Vec<Vec<u8>>,
 }
 
-impl<'a> MockPersonSensorBus<'a> {
-    pub fn new(mode: u8, payload: &'a [u8; 39]) -> Self {
+impl MockPersonSensorBus {
+    pub fn new(mode: u8, payload: [u8; 39]) -> Self {
         // Set the mode to 1 to indicate that the sensor is in continuous
-        Self { mode, payload }
+        Self::with_payloads(mode, &[payload])
+    }
+
+    /// Returns each payload in turn, one per read. The last payload is repeated once the others
+    /// have been read. Reads before the first write, such as the builder's probe, return
+    /// [`NO_FACES`] instead, unless another probe payload is set.
+    pub fn with_payloads(mode: u8, payloads: &[[u8; 39]]) -> Self {
+        Self {
+            mode,
+            payloads: payloads.to_vec(),
+            next_payload: 0,
+            probe_payload: NO_FACES,
+            writes: Vec::new(),
+        }
+    }
+
+    /// Returns `payload` for reads before the first write, such as the builder's probe, instead of
+    /// [`NO_FACES`].
+    pub fn with_probe_payload(self, payload: [u8; 39]) -> Self {
+        Self {
+            probe_payload: payload,
+            ..self
+        }
+    }
+
+    pub fn mode(&self) -> u8 {
+        self.mode
+    }
+
+    /// Every write received by the mock, in order.
+    pub fn writes(&self) -> &[Vec<u8>] {
+        &self.writes
     }
 
     fn mock_write(&mut self, data: &[u8]) {
+        self.writes.push(data.to_vec());
         match data[0] {
             0x01 => self.mode = data[1],
             0x03 => {
@@ -22,8 +121,56 @@ impl<'a> MockPersonSensorBus<'a> {
         }
     }
 
-    fn mock_read(&self, buffer: &mut [u8]) {
-        buffer.copy_from_slice(&self.payload[..buffer.len()]);
+    fn mock_read(&mut self, buffer: &mut [u8]) {
+        if self.writes.is_empty() {
+            buffer.copy_from_slice(&self.probe_payload[..buffer.len()]);
+            return;
+        }
+        let payload = &self.payloads[self.next_payload];
+        buffer.copy_from_slice(&payload[..buffer.len()]);
+        self.next_payload = (self.next_payload + 1).min(self.payloads.len() - 1);
+    }
+}
+
+/// A clock that only moves when told to, either directly or by waiting on a [`MockDelay`].
+#[derive(Debug, Default)]
+pub struct MockClock {
+    now_ms: Cell<u64>,
+}
+
+impl MockClock {
+    pub fn now_ms(&self) -> u64 {
+        self.now_ms.get()
+    }
+
+    pub fn set_ms(&self, now_ms: u64) {
+        self.now_ms.set(now_ms);
+    }
+
+    pub fn advance_ms(&self, ms: u64) {
+        self.now_ms.set(self.now_ms.get() + ms);
+    }
+}
+
+/// A delay that returns immediately, advancing a [`MockClock`] by the requested duration.
+#[derive(Debug)]
+pub struct MockDelay<'a> {
+    clock: &'a MockClock,
+}
+
+impl<'a> MockDelay<'a> {
+    pub fn new(clock: &'a MockClock) -> Self {
+        Self { clock }
+    }
+}
+
+impl DelayNs for MockDelay<'_> {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.clock.advance_ms(u64::from(ns.div_ceil(1_000_000)));
+    }
+
+    async fn delay_ms(&mut self, ms: u32) {
+        self.clock.advance_ms(u64::from(ms));
     }
 }
 
@@ -38,16 +185,16 @@ impl i2c::Error for MockError {
     }
 }
 
-impl ErrorType for MockPersonSensorBus<'_> {
+impl ErrorType for MockPersonSensorBus {
     type Error = MockError;
 }
 
-impl I2c<SevenBitAddress> for MockPersonSensorBus<'_> {
-    async fn transaction(
+impl MockPersonSensorBus {
+    fn mock_transaction(
         &mut self,
         address: SevenBitAddress,
         operations: &mut [Operation<'_>],
-    ) -> Result<(), Self::Error> {
+    ) -> Result<(), MockError> {
         if address != 0x62 {
             return Err(MockError::IoError);
         }
@@ -61,3 +208,76 @@ impl I2c<SevenBitAddress> for MockPersonSensorBus<'_> {
         Ok(())
     }
 }
+
+impl I2c<SevenBitAddress> for MockPersonSensorBus {
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.mock_transaction(address, operations)
+    }
+}
+
+/// The same bus, for blocking shared bus implementations.
+impl embedded_hal::i2c::I2c<SevenBitAddress> for MockPersonSensorBus {
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.mock_transaction(address, operations)
+    }
+}
+
+/// A delay that returns immediately.
+#[derive(Debug, Default)]
+pub struct NoopDelay;
+
+impl DelayNs for NoopDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
+
+/// An interrupt pin with a fixed level. Waiting for a level the pin isn't at never completes.
+/// Waiting for a rising edge only completes if `rising_edge` is set, as when the next frame
+/// raises the pin.
+#[derive(Debug)]
+pub struct MockInterrupt {
+    pub high: bool,
+    pub rising_edge: bool,
+}
+
+impl DigitalErrorType for MockInterrupt {
+    type Error = core::convert::Infallible;
+}
+
+impl Wait for MockInterrupt {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        if !self.high {
+            pending::<()>().await;
+        }
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        if self.high {
+            pending::<()>().await;
+        }
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        if !self.rising_edge {
+            pending::<()>().await;
+        }
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        pending().await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        pending().await
+    }
+}
@@ -0,0 +1,131 @@
+use person_sensor::{
+    crossing::{Crossing, Direction, Line, LineCounter},
+    tracker::TrackerConfig,
+    Face, Recognition,
+};
+
+/// A 20 by 20 face centered on `(x, y)`.
+fn face_at(x: u8, y: u8) -> Face {
+    Face {
+        box_confidence: 90,
+        box_left: x - 10,
+        box_top: y - 10,
+        box_right: x + 10,
+        box_bottom: y + 10,
+        recognition: Recognition::Unknown,
+        is_facing: true,
+    }
+}
+
+/// A horizontal line across the middle of the view, between x = 50 and x = 200.
+const LINE: Line = Line {
+    start: (50, 128),
+    end: (200, 128),
+};
+
+fn counter() -> LineCounter<4> {
+    LineCounter::new(LINE, TrackerConfig::default())
+}
+
+/// Feeds one frame per position of a single person, returning the crossings.
+fn walk(counter: &mut LineCounter<4>, path: &[(u8, u8)]) -> Vec<Crossing> {
+    path.iter()
+        .flat_map(|&(x, y)| counter.update(&[face_at(x, y)]))
+        .collect()
+}
+
+#[test]
+fn counts_both_directions() {
+    let mut counter = counter();
+    let crossings = walk(
+        &mut counter,
+        &[(100, 80), (100, 110), (100, 140), (100, 170)],
+    );
+    assert_eq!(crossings.len(), 1);
+    assert_eq!(crossings[0].direction, Direction::In);
+    assert_eq!(
+        crossings[0].track,
+        counter.tracker().tracks()[0].id(),
+        "The crossing is reported for the person's track"
+    );
+
+    walk(&mut counter, &[(100, 150), (100, 120), (100, 90)]);
+    assert_eq!(counter.count(Direction::In), 1);
+    assert_eq!(counter.count(Direction::Out), 1);
+}
+
+#[test]
+fn reversing_the_line_reverses_directions() {
+    let mut counter = LineCounter::<4>::new(
+        Line {
+            start: LINE.end,
+            end: LINE.start,
+        },
+        TrackerConfig::default(),
+    );
+    walk(&mut counter, &[(100, 110), (100, 140)]);
+    assert_eq!(counter.count(Direction::Out), 1);
+}
+
+#[test]
+fn ignores_crossings_beyond_the_ends() {
+    let mut counter = counter();
+    walk(&mut counter, &[(20, 110), (20, 140)]);
+    walk(&mut counter, &[(230, 140), (230, 110)]);
+    assert_eq!(counter.count(Direction::In), 0);
+    assert_eq!(counter.count(Direction::Out), 0);
+}
+
+#[test]
+fn lingering_on_the_line() {
+    let mut counter = counter();
+    // Stepping onto the line and back isn't a crossing
+    walk(
+        &mut counter,
+        &[(100, 110), (100, 128), (100, 128), (100, 110)],
+    );
+    assert_eq!(counter.count(Direction::In), 0);
+
+    // Stepping onto the line and off the other side is
+    walk(&mut counter, &[(100, 128), (100, 140)]);
+    assert_eq!(counter.count(Direction::In), 1);
+}
+
+#[test]
+fn counts_several_people() {
+    let mut counter = counter();
+    counter.update(&[face_at(80, 110), face_at(160, 150)]);
+    let crossings = counter.update(&[face_at(82, 140), face_at(158, 115)]);
+    assert_eq!(crossings.len(), 2);
+    assert_eq!(counter.count(Direction::In), 1);
+    assert_eq!(counter.count(Direction::Out), 1);
+}
+
+#[test]
+fn missed_frames_do_not_reset_the_side() {
+    let mut counter = counter();
+    counter.update(&[face_at(100, 110)]);
+    counter.update(&[]);
+    counter.update(&[face_at(100, 140)]);
+    assert_eq!(counter.count(Direction::In), 1);
+}
+
+#[test]
+fn new_people_are_not_counted_on_arrival() {
+    let mut counter = counter();
+    counter.update(&[face_at(100, 140)]);
+    assert_eq!(counter.count(Direction::In), 0);
+}
+
+#[test]
+fn reset_keeps_people_in_progress() {
+    let mut counter = counter();
+    walk(&mut counter, &[(100, 100), (100, 140), (100, 110)]);
+    counter.update(&[face_at(100, 110)]);
+    counter.reset();
+    assert_eq!(counter.count(Direction::In), 0);
+    assert_eq!(counter.count(Direction::Out), 0);
+
+    counter.update(&[face_at(100, 140)]);
+    assert_eq!(counter.count(Direction::In), 1);
+}
@@ -0,0 +1,102 @@
+mod common;
+use common::{payload, MockPersonSensorBus};
+use person_sensor::{
+    encode_frame, Detections, Face, PersonID, PersonSensorBuilder, ReadError, Recognition,
+};
+use proptest::prelude::*;
+
+/// Reads `frame` through the driver, as if it had been returned by the sensor.
+fn decode(frame: [u8; 39]) -> Result<Detections, ReadError<common::MockError>> {
+    tokio_test::block_on(async {
+        let i2c = MockPersonSensorBus::new(1, frame);
+        let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, true)
+            .build()
+            .await
+            .unwrap();
+        person_sensor.get_detections().await
+    })
+}
+
+/// Replaces the checksum of `frame` with a valid one.
+fn with_checksum(mut frame: [u8; 39]) -> [u8; 39] {
+    let checksum = crc16::State::<crc16::MCRF4XX>::calculate(&frame[..37]);
+    frame[37..].copy_from_slice(&checksum.to_le_bytes());
+    frame
+}
+
+fn any_face() -> impl Strategy<Value = Face> {
+    let recognition = prop_oneof![
+        Just(Recognition::Unknown),
+        Just(Recognition::NotLargestFace),
+        (0..8u8, 1..=100u8).prop_map(|(id, confidence)| Recognition::Identified {
+            id: PersonID::new(id).unwrap(),
+            confidence,
+        }),
+    ];
+    (any::<[u8; 5]>(), recognition, any::<bool>()).prop_map(|(bytes, recognition, is_facing)| {
+        Face {
+            box_confidence: bytes[0],
+            box_left: bytes[1],
+            box_top: bytes[2],
+            box_right: bytes[3],
+            box_bottom: bytes[4],
+            recognition,
+            is_facing,
+        }
+    })
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_frames_never_panic(frame in any::<[u8; 39]>()) {
+        match decode(frame) {
+            Ok(faces) => prop_assert_eq!(faces.len(), usize::from(frame[4]).min(4)),
+            Err(error) => prop_assert_eq!(error, ReadError::ChecksumMismatch),
+        }
+    }
+
+    #[test]
+    fn valid_checksums_decode_the_reported_count(frame in any::<[u8; 39]>()) {
+        let frame = with_checksum(frame);
+        let faces = decode(frame).unwrap();
+        prop_assert_eq!(faces.len(), usize::from(frame[4]).min(4));
+    }
+
+    #[test]
+    fn corrupted_bits_are_detected(
+        frame in any::<[u8; 39]>(),
+        bit in 0..39 * 8usize,
+    ) {
+        let mut frame = with_checksum(frame);
+        frame[bit / 8] ^= 1 << (bit % 8);
+        prop_assert_eq!(decode(frame), Err(ReadError::ChecksumMismatch));
+    }
+
+    #[test]
+    fn encoded_faces_round_trip(records in prop::collection::vec(any::<[u8; 8]>(), 0..=4)) {
+        let faces = decode(payload(&records)).unwrap();
+        prop_assert_eq!(faces.len(), records.len());
+
+        for (face, record) in faces.iter().zip(&records) {
+            let box_confidence = face.box_confidence;
+            let is_facing = face.is_facing;
+            prop_assert_eq!(
+                [box_confidence, face.box_left, face.box_top, face.box_right, face.box_bottom],
+                [record[0], record[1], record[2], record[3], record[4]]
+            );
+            prop_assert_eq!(is_facing, record[7] > 0);
+
+            let expected_id = (record[6] < 8 && (record[5] as i8) > 0).then_some(record[6]);
+            prop_assert_eq!(face.id().map(u8::from), expected_id);
+        }
+    }
+
+    #[test]
+    fn decoded_faces_encode_to_the_same_frame(
+        faces in prop::collection::vec(any_face(), 0..=4),
+    ) {
+        let frame = encode_frame(&faces);
+        let decoded = decode(frame).unwrap();
+        prop_assert_eq!(encode_frame(&decoded), frame);
+    }
+}
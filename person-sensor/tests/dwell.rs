@@ -0,0 +1,169 @@
+mod common;
+
+use common::MockClock;
+use person_sensor::{
+    dwell::{DwellConfig, DwellMonitor, Histogram},
+    tracker::TrackerConfig,
+    Face, Recognition,
+};
+
+/// A 20 by 20 face centered on `(x, y)`.
+fn face_at(x: u8, y: u8, is_facing: bool) -> Face {
+    Face {
+        box_confidence: 90,
+        box_left: x - 10,
+        box_top: y - 10,
+        box_right: x + 10,
+        box_bottom: y + 10,
+        recognition: Recognition::Unknown,
+        is_facing,
+    }
+}
+
+const CONFIG: DwellConfig = DwellConfig {
+    tracker: TrackerConfig {
+        max_distance: 30,
+        max_missed_frames: 1,
+    },
+    bin_ms: 1_000,
+};
+
+#[test]
+fn histogram_bins() {
+    let mut histogram = Histogram::<3>::new(1_000);
+    for duration_ms in [0, 999, 1_000, 2_500, 60_000] {
+        histogram.record(duration_ms);
+    }
+    assert_eq!(histogram.counts(), &[2, 1, 2]);
+    assert_eq!(histogram.total(), 5);
+
+    histogram.clear();
+    assert_eq!(histogram.total(), 0);
+}
+
+#[test]
+fn times_dwell_and_facing() {
+    let clock = MockClock::default();
+    let mut monitor = DwellMonitor::<_, 4, 4>::new(|| clock.now_ms(), CONFIG);
+
+    // Facing for the first second, then looking away for half a second
+    for (time_ms, is_facing) in [(0, true), (500, true), (1_000, false), (1_500, false)] {
+        clock.set_ms(time_ms);
+        assert!(monitor.update(&[face_at(100, 100, is_facing)]).is_empty());
+    }
+    let current: Vec<_> = monitor.current().collect();
+    assert_eq!(current.len(), 1);
+    assert_eq!(current[0].dwell_ms, 1_500);
+    assert_eq!(current[0].facing_ms, 1_000);
+
+    // The visit ends when the track is lost
+    clock.set_ms(2_000);
+    assert!(monitor.update(&[]).is_empty());
+    clock.set_ms(2_500);
+    let finished = monitor.update(&[]);
+    assert_eq!(finished.len(), 1);
+    assert_eq!(finished[0].dwell_ms, 1_500);
+    assert_eq!(finished[0].facing_ms, 1_000);
+    assert_eq!(monitor.current().count(), 0);
+
+    let report = monitor.report();
+    assert_eq!(report.visits, 1);
+    assert_eq!(report.dwell_ms, 1_500);
+    assert_eq!(report.facing_ms, 1_000);
+    assert_eq!(report.dwell.counts(), &[0, 1, 0, 0]);
+    assert_eq!(report.facing.counts(), &[0, 1, 0, 0]);
+}
+
+#[test]
+fn missed_frames_are_part_of_the_visit() {
+    let clock = MockClock::default();
+    let mut monitor = DwellMonitor::<_, 4, 4>::new(|| clock.now_ms(), CONFIG);
+
+    monitor.update(&[face_at(100, 100, true)]);
+    clock.set_ms(500);
+    monitor.update(&[]);
+    clock.set_ms(1_000);
+    monitor.update(&[face_at(102, 100, true)]);
+
+    let visit = monitor.current().next().unwrap();
+    assert_eq!(visit.dwell_ms, 1_000);
+    assert_eq!(visit.facing_ms, 0);
+}
+
+#[test]
+fn facing_time_skips_missed_frames() {
+    let clock = MockClock::default();
+    let mut monitor = DwellMonitor::<_, 4, 4>::new(|| clock.now_ms(), CONFIG);
+
+    for (now_ms, faces) in [
+        (0, &[face_at(100, 100, true)][..]),
+        (500, &[face_at(100, 100, true)]),
+        (1_000, &[]),
+        (1_500, &[face_at(102, 100, true)]),
+        (2_000, &[face_at(102, 100, false)]),
+    ] {
+        clock.set_ms(now_ms);
+        monitor.update(faces);
+    }
+
+    // Facing from 0 to 500 and from 1500 to 2000. The gap around the missed frame is skipped
+    let visit = monitor.current().next().unwrap();
+    assert_eq!(visit.dwell_ms, 2_000);
+    assert_eq!(visit.facing_ms, 1_000);
+}
+
+#[test]
+fn times_people_separately() {
+    let clock = MockClock::default();
+    let mut monitor = DwellMonitor::<_, 4, 4>::new(|| clock.now_ms(), CONFIG);
+
+    monitor.update(&[face_at(50, 100, true)]);
+    clock.set_ms(1_000);
+    monitor.update(&[face_at(50, 100, true), face_at(200, 100, false)]);
+    clock.set_ms(3_000);
+    monitor.update(&[face_at(50, 100, false), face_at(200, 100, false)]);
+
+    let mut visits: Vec<_> = monitor.current().collect();
+    visits.sort_by_key(|visit| visit.track);
+    assert_eq!(
+        visits
+            .iter()
+            .map(|visit| (visit.dwell_ms, visit.facing_ms))
+            .collect::<Vec<_>>(),
+        [(3_000, 3_000), (2_000, 0)]
+    );
+
+    for _ in 0..2 {
+        monitor.update(&[]);
+    }
+    let report = monitor.report();
+    assert_eq!(report.visits, 2);
+    assert_eq!(report.dwell.counts(), &[0, 0, 1, 1]);
+    assert_eq!(report.facing.counts(), &[1, 0, 0, 1]);
+}
+
+#[test]
+fn reset_starts_a_new_period() {
+    let clock = MockClock::default();
+    let mut monitor = DwellMonitor::<_, 4, 4>::new(|| clock.now_ms(), CONFIG);
+
+    // One visit finishes in the first period, and another is still in progress
+    monitor.update(&[face_at(50, 100, true)]);
+    clock.set_ms(1_000);
+    monitor.update(&[face_at(200, 100, true)]);
+    clock.set_ms(2_000);
+    monitor.update(&[face_at(200, 100, true)]);
+    assert_eq!(monitor.report().visits, 1);
+
+    monitor.reset();
+    assert_eq!(monitor.report().visits, 0);
+    assert_eq!(monitor.report().dwell.total(), 0);
+    assert_eq!(monitor.report().dwell.bin_ms(), 1_000);
+
+    // The visit in progress counts towards the period it finishes in
+    for _ in 0..2 {
+        monitor.update(&[]);
+    }
+    assert_eq!(monitor.report().visits, 1);
+    assert_eq!(monitor.report().dwell_ms, 1_000);
+}
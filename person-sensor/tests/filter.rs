@@ -0,0 +1,160 @@
+mod common;
+
+use common::{face, MockPersonSensorBus, NoopDelay, TWO_FACES};
+use person_sensor::{
+    filter::{DetectionFilter, MAX_IGNORE_ZONES},
+    zone::{Zone, ZoneError},
+    BoundingBox, Detections, Face, PersonSensorBuilder,
+};
+
+fn sized(width: u8, height: u8) -> Face {
+    Face {
+        box_left: 100,
+        box_top: 100,
+        box_right: 100 + width,
+        box_bottom: 100 + height,
+        ..face()
+    }
+}
+
+#[test]
+fn keeps_everything_by_default() {
+    let filter = DetectionFilter::new();
+    assert!(filter.keeps(&face()));
+    assert!(filter.keeps(&Face {
+        box_confidence: 0,
+        is_facing: false,
+        ..sized(0, 150)
+    }));
+}
+
+#[test]
+fn minimum_confidence() {
+    let filter = DetectionFilter::new().with_min_confidence(60);
+    assert!(filter.keeps(&Face {
+        box_confidence: 60,
+        ..face()
+    }));
+    assert!(!filter.keeps(&Face {
+        box_confidence: 59,
+        ..face()
+    }));
+}
+
+#[test]
+fn size_range() {
+    let filter = DetectionFilter::new().with_size(10, 100);
+    assert!(filter.keeps(&sized(10, 100)));
+    assert!(!filter.keeps(&sized(9, 50)));
+    assert!(!filter.keeps(&sized(50, 9)));
+    assert!(!filter.keeps(&sized(101, 50)));
+    assert!(!filter.keeps(&sized(50, 101)));
+
+    // Swapped bounds don't drop every face
+    assert_eq!(DetectionFilter::new().with_size(100, 10), filter);
+}
+
+#[test]
+fn facing_only() {
+    let filter = DetectionFilter::new().with_facing_only(true);
+    assert!(filter.keeps(&face()));
+    assert!(!filter.keeps(&Face {
+        is_facing: false,
+        ..face()
+    }));
+}
+
+#[test]
+fn aspect_ratio() {
+    let filter = DetectionFilter::new().with_max_aspect_ratio_percent(150);
+    assert!(filter.keeps(&sized(40, 60)));
+    assert!(filter.keeps(&sized(60, 40)));
+    assert!(!filter.keeps(&sized(40, 61)));
+    assert!(!filter.keeps(&sized(0, 10)));
+}
+
+#[test]
+fn ignore_zones() {
+    let poster = Zone::rect(
+        BoundingBox {
+            left: 0,
+            top: 0,
+            right: 60,
+            bottom: 60,
+        },
+        50,
+    );
+    let filter = DetectionFilter::new()
+        .with_ignore_zone(poster.clone())
+        .unwrap();
+    assert!(filter.keeps(&face()));
+    assert!(!filter.keeps(&Face {
+        box_left: 10,
+        box_top: 10,
+        box_right: 40,
+        box_bottom: 40,
+        ..face()
+    }));
+
+    let mut filter = DetectionFilter::new();
+    for _ in 0..MAX_IGNORE_ZONES {
+        filter = filter.with_ignore_zone(poster.clone()).unwrap();
+    }
+    assert_eq!(filter.with_ignore_zone(poster), Err(ZoneError::Full));
+}
+
+#[test]
+fn checks_combine() {
+    let filter = DetectionFilter::new()
+        .with_min_confidence(60)
+        .with_facing_only(true);
+    assert!(!filter.keeps(&Face {
+        box_confidence: 90,
+        is_facing: false,
+        ..face()
+    }));
+    assert!(!filter.keeps(&Face {
+        box_confidence: 50,
+        ..face()
+    }));
+}
+
+#[test]
+fn applies_to_recorded_frames() {
+    let filter = DetectionFilter::new().with_min_confidence(60);
+    let mut faces = Detections::new();
+    for box_confidence in [90, 40, 70] {
+        faces
+            .push(Face {
+                box_confidence,
+                ..face()
+            })
+            .unwrap();
+    }
+    filter.apply(&mut faces);
+    let confidences: Vec<_> = faces.iter().map(|face| face.box_confidence).collect();
+    assert_eq!(confidences, [90, 70]);
+}
+
+#[tokio::test]
+async fn driver_filters_detections() {
+    // The second face of `TWO_FACES` has a confidence of 94
+    let i2c = MockPersonSensorBus::new(1, TWO_FACES);
+    let mut sensor = PersonSensorBuilder::new_continuous(i2c, true)
+        .with_filter(DetectionFilter::new().with_min_confidence(95))
+        .build()
+        .await
+        .unwrap();
+    let faces = sensor.get_detections().await.unwrap();
+    assert_eq!(faces.len(), 1);
+    assert_eq!(faces[0].box_confidence, 99);
+
+    sensor.set_filter(DetectionFilter::new());
+    assert_eq!(sensor.get_detections().await.unwrap().len(), 2);
+
+    // The filter is kept across mode changes
+    sensor.set_filter(DetectionFilter::new().with_min_confidence(95));
+    let mut sensor = sensor.into_standby_mode().await.unwrap();
+    let faces = sensor.capture_once(&mut NoopDelay).await.unwrap();
+    assert_eq!(faces.len(), 1);
+}
@@ -1,31 +1,10 @@
 mod common;
-use common::MockPersonSensorBus;
-use person_sensor::{PersonID, PersonSensorBuilder};
-
-const NO_FACES: [u8; 39] = [
-    0x00, 0x00, 0x21, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x25, 0x37,
-];
-const ONE_FACE: [u8; 39] = [
-    0x00, 0x00, 0x21, 0x00, 0x01, 0x63, 0x7c, 0x80, 0x95, 0xaa, 0x43, 0x00, 0x01, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x15, 0x8b,
-];
-const TWO_FACES: [u8; 39] = [
-    0x00, 0x00, 0x21, 0x00, 0x02, 0x63, 0x3e, 0x5e, 0x62, 0x9e, 0x4e, 0x00, 0x01, 0x5e, 0x79, 0x67,
-    0x8e, 0x88, 0x38, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x00, 0xb9, 0xf9,
-];
-const BAD_CHECKSUM: [u8; 39] = [
-    0x00, 0x00, 0x21, 0x00, 0x02, 0x63, 0x3e, 0x5e, 0x62, 0x9e, 0x4e, 0x00, 0x01, 0x5e, 0x79, 0x67,
-    0x8e, 0x88, 0x38, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x00, 0x01, 0xb9, 0xf9,
-];
+use common::{MockPersonSensorBus, NoopDelay};
+use person_sensor::{encode_frame, Face, PersonID, PersonSensorBuilder, Recognition};
 
 #[tokio::test]
 async fn no_faces() {
-    let i2c = MockPersonSensorBus::new(1, &NO_FACES);
+    let i2c = MockPersonSensorBus::new(1, common::NO_FACES);
 
     let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, false)
         .build()
@@ -37,7 +16,7 @@ async fn no_faces() {
 
 #[tokio::test]
 async fn one_face() {
-    let i2c = MockPersonSensorBus::new(1, &ONE_FACE);
+    let i2c = MockPersonSensorBus::new(1, common::ONE_FACE);
 
     let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, false)
         .build()
@@ -50,14 +29,19 @@ async fn one_face() {
     assert_eq!(detections[0].box_top, 0x80);
     assert_eq!(detections[0].box_right, 0x95);
     assert_eq!(detections[0].box_bottom, 0xaa);
-    assert_eq!(detections[0].id_confidence, 0x43);
-    assert_eq!(detections[0].id, Some(PersonID::new(0).unwrap()));
+    assert_eq!(
+        detections[0].recognition,
+        Recognition::Identified {
+            id: PersonID::new(0).unwrap(),
+            confidence: 0x43,
+        }
+    );
     assert!(detections[0].is_facing);
 }
 
 #[tokio::test]
 async fn two_faces() {
-    let i2c = MockPersonSensorBus::new(1, &TWO_FACES);
+    let i2c = MockPersonSensorBus::new(1, common::TWO_FACES);
     let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, false)
         .build()
         .await
@@ -71,8 +55,13 @@ async fn two_faces() {
     assert_eq!(detections[0].box_top, 0x5e);
     assert_eq!(detections[0].box_right, 0x62);
     assert_eq!(detections[0].box_bottom, 0x9e);
-    assert_eq!(detections[0].id_confidence, 0x4e);
-    assert_eq!(detections[0].id, Some(PersonID::new(0).unwrap()));
+    assert_eq!(
+        detections[0].recognition,
+        Recognition::Identified {
+            id: PersonID::new(0).unwrap(),
+            confidence: 0x4e,
+        }
+    );
     assert!(detections[0].is_facing);
 
     // Second face
@@ -81,14 +70,19 @@ async fn two_faces() {
     assert_eq!(detections[1].box_top, 0x67);
     assert_eq!(detections[1].box_right, 0x8e);
     assert_eq!(detections[1].box_bottom, 0x88);
-    assert_eq!(detections[1].id_confidence, 0x38);
-    assert_eq!(detections[1].id, Some(PersonID::new_unchecked(0)));
+    assert_eq!(
+        detections[1].recognition,
+        Recognition::Identified {
+            id: PersonID::new(0).unwrap(),
+            confidence: 0x38,
+        }
+    );
     assert!(detections[1].is_facing);
 }
 
 #[tokio::test]
 async fn bad_checksum_continuous() {
-    let i2c = MockPersonSensorBus::new(1, &BAD_CHECKSUM);
+    let i2c = MockPersonSensorBus::new(1, common::BAD_CHECKSUM);
 
     let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, false)
         .build()
@@ -103,14 +97,16 @@ async fn bad_checksum_continuous() {
 
 #[tokio::test]
 async fn bad_checksum_standby() {
-    let i2c = MockPersonSensorBus::new(1, &BAD_CHECKSUM);
+    let i2c = MockPersonSensorBus::new(1, common::BAD_CHECKSUM);
 
     let mut person_sensor = PersonSensorBuilder::new_standby(i2c, false)
         .build()
         .await
         .unwrap();
 
-    if Err(person_sensor::ReadError::ChecksumMismatch) == person_sensor.capture_once().await {
+    if Err(person_sensor::ReadError::ChecksumMismatch)
+        == person_sensor.capture_once(&mut NoopDelay).await
+    {
     } else {
         panic!("Expected ChecksumMismatch error");
     }
@@ -118,7 +114,7 @@ async fn bad_checksum_standby() {
 
 #[tokio::test]
 async fn set_mode_on_init() {
-    let i2c = MockPersonSensorBus::new(0, &NO_FACES);
+    let i2c = MockPersonSensorBus::new(0, common::NO_FACES);
 
     let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, false)
         .build()
@@ -126,18 +122,18 @@ async fn set_mode_on_init() {
         .unwrap();
     _ = person_sensor.get_detections().await.unwrap();
 
-    let i2c = MockPersonSensorBus::new(1, &NO_FACES);
+    let i2c = MockPersonSensorBus::new(1, common::NO_FACES);
 
     let mut person_sensor = PersonSensorBuilder::new_standby(i2c, false)
         .build()
         .await
         .unwrap();
-    _ = person_sensor.capture_once().await.unwrap();
+    _ = person_sensor.capture_once(&mut NoopDelay).await.unwrap();
 }
 
 #[tokio::test]
 async fn switch_mode() {
-    let i2c = MockPersonSensorBus::new(0, &NO_FACES);
+    let i2c = MockPersonSensorBus::new(0, common::NO_FACES);
 
     let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, false)
         .build()
@@ -146,8 +142,40 @@ async fn switch_mode() {
     _ = person_sensor.get_detections().await.unwrap();
 
     let mut person_sensor = person_sensor.into_standby_mode().await.unwrap();
-    _ = person_sensor.capture_once().await.unwrap();
+    _ = person_sensor.capture_once(&mut NoopDelay).await.unwrap();
 
     let mut person_sensor = person_sensor.into_continuous_mode().await.unwrap();
     _ = person_sensor.get_detections().await.unwrap();
 }
+
+#[test]
+fn encoder_matches_captured_frames() {
+    assert_eq!(encode_frame(&[]), common::NO_FACES);
+    assert_eq!(encode_frame(&[common::face()]), common::ONE_FACE);
+
+    let person = |confidence| Recognition::Identified {
+        id: PersonID::new(0).unwrap(),
+        confidence,
+    };
+    let faces = [
+        Face {
+            box_confidence: 99,
+            box_left: 62,
+            box_top: 94,
+            box_right: 98,
+            box_bottom: 158,
+            recognition: person(78),
+            is_facing: true,
+        },
+        Face {
+            box_confidence: 94,
+            box_left: 121,
+            box_top: 103,
+            box_right: 142,
+            box_bottom: 136,
+            recognition: person(56),
+            is_facing: true,
+        },
+    ];
+    assert_eq!(encode_frame(&faces), common::TWO_FACES);
+}
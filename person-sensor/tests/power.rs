@@ -0,0 +1,159 @@
+mod common;
+use common::{MockClock, MockDelay, MockPersonSensorBus, NoopDelay, NO_FACES, ONE_FACE};
+use person_sensor::{
+    power::{ManagedSensor, PowerConfig, PowerManager, PowerState},
+    CaptureWait, PersonSensorBuilder,
+};
+
+const CONFIG: PowerConfig = PowerConfig {
+    standby_interval_ms: 1_000,
+    continuous_interval_ms: 100,
+    idle_timeout_ms: 500,
+};
+
+#[tokio::test]
+async fn stays_in_standby_without_faces() {
+    let mut i2c = MockPersonSensorBus::new(1, NO_FACES);
+    let clock = MockClock::default();
+
+    let sensor = PersonSensorBuilder::new_standby(&mut i2c, false)
+        .build()
+        .await
+        .unwrap();
+    let mut manager = PowerManager::new(sensor, || clock.now_ms(), CONFIG);
+
+    for _ in 0..3 {
+        assert!(manager.poll(&mut NoopDelay).await.unwrap().is_empty());
+        assert_eq!(manager.state(), PowerState::Standby);
+        assert_eq!(manager.next_poll_ms(), clock.now_ms() + 1_000);
+        clock.advance_ms(1_000);
+    }
+
+    _ = manager.release();
+    let captures = i2c.writes().iter().filter(|w| w[0] == 0x03).count();
+    assert_eq!(captures, 3);
+    assert_eq!(i2c.mode(), 0);
+}
+
+#[tokio::test]
+async fn wakes_on_detection_and_sleeps_after_timeout() {
+    let payloads = [
+        NO_FACES, ONE_FACE, ONE_FACE, NO_FACES, NO_FACES, NO_FACES, NO_FACES, NO_FACES,
+    ];
+    let mut i2c = MockPersonSensorBus::with_payloads(1, &payloads);
+    let clock = MockClock::default();
+
+    let sensor = PersonSensorBuilder::new_standby(&mut i2c, false)
+        .build()
+        .await
+        .unwrap();
+    let mut manager = PowerManager::new(sensor, || clock.now_ms(), CONFIG);
+
+    // t = 0: nobody present
+    assert!(manager.poll(&mut NoopDelay).await.unwrap().is_empty());
+    assert_eq!(manager.state(), PowerState::Standby);
+    assert_eq!(manager.next_poll_ms(), 1_000);
+
+    // t = 1000: a face appears, switch to continuous
+    clock.set_ms(1_000);
+    assert_eq!(manager.poll(&mut NoopDelay).await.unwrap().len(), 1);
+    assert_eq!(manager.state(), PowerState::Continuous);
+    assert_eq!(manager.next_poll_ms(), 1_100);
+
+    // t = 1100: still present
+    clock.set_ms(1_100);
+    assert_eq!(manager.poll(&mut NoopDelay).await.unwrap().len(), 1);
+
+    // t = 1200 - 1500: empty, but within the idle timeout
+    for now in [1_200, 1_300, 1_400, 1_500] {
+        clock.set_ms(now);
+        assert!(manager.poll(&mut NoopDelay).await.unwrap().is_empty());
+        assert_eq!(manager.state(), PowerState::Continuous);
+    }
+
+    // t = 1600: 500ms since the last detection
+    clock.set_ms(1_600);
+    assert!(manager.poll(&mut NoopDelay).await.unwrap().is_empty());
+    assert_eq!(manager.state(), PowerState::Standby);
+    assert_eq!(manager.next_poll_ms(), 2_600);
+
+    let ManagedSensor::Standby(_) = manager.release() else {
+        panic!("Expected the sensor to be released in standby mode");
+    };
+
+    let modes: Vec<u8> = i2c
+        .writes()
+        .iter()
+        .filter(|w| w[0] == 0x01)
+        .map(|w| w[1])
+        .collect();
+    // Set by the builder, then by the manager
+    assert_eq!(modes, [0, 1, 0]);
+    assert_eq!(i2c.mode(), 0);
+}
+
+#[tokio::test]
+async fn wait_and_poll_follows_intervals() {
+    let payloads = [NO_FACES, NO_FACES, ONE_FACE, NO_FACES];
+    let i2c = MockPersonSensorBus::with_payloads(1, &payloads);
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+
+    let sensor = PersonSensorBuilder::new_standby(i2c, false)
+        .with_capture_wait(CaptureWait::Delay { ms: 200 })
+        .build()
+        .await
+        .unwrap();
+    let mut manager = PowerManager::new(sensor, || clock.now_ms(), CONFIG);
+
+    // Each standby capture waits 200ms for the sensor before reading results
+    manager.wait_and_poll(&mut delay).await.unwrap();
+    assert_eq!(clock.now_ms(), 200);
+    assert_eq!(manager.next_poll_ms(), 1_200);
+
+    manager.wait_and_poll(&mut delay).await.unwrap();
+    assert_eq!(clock.now_ms(), 1_400);
+
+    manager.wait_and_poll(&mut delay).await.unwrap();
+    assert_eq!(clock.now_ms(), 2_600);
+    assert_eq!(manager.state(), PowerState::Continuous);
+
+    // Continuous reads don't wait
+    manager.wait_and_poll(&mut delay).await.unwrap();
+    assert_eq!(clock.now_ms(), 2_700);
+}
+
+#[tokio::test]
+async fn continuous_sensor_times_out() {
+    let i2c = MockPersonSensorBus::new(0, NO_FACES);
+    let clock = MockClock::default();
+
+    let sensor = PersonSensorBuilder::new_continuous(i2c, false)
+        .build()
+        .await
+        .unwrap();
+    let mut manager = PowerManager::from_continuous(sensor, || clock.now_ms(), CONFIG);
+
+    manager.poll(&mut NoopDelay).await.unwrap();
+    assert_eq!(manager.state(), PowerState::Continuous);
+
+    clock.set_ms(500);
+    manager.poll(&mut NoopDelay).await.unwrap();
+    assert_eq!(manager.state(), PowerState::Standby);
+}
+
+#[tokio::test]
+async fn schedule_saturates_at_the_end_of_time() {
+    let i2c = MockPersonSensorBus::new(0, NO_FACES);
+    let clock = MockClock::default();
+    clock.set_ms(u64::MAX - 10);
+
+    let sensor = PersonSensorBuilder::new_standby(i2c, false)
+        .build()
+        .await
+        .unwrap();
+    let mut manager = PowerManager::new(sensor, || clock.now_ms(), CONFIG);
+
+    manager.poll(&mut NoopDelay).await.unwrap();
+    assert_eq!(manager.next_poll_ms(), u64::MAX);
+}
@@ -0,0 +1,82 @@
+mod common;
+use common::{MockPersonSensorBus, BAD_CHECKSUM, NO_FACES, ONE_FACE};
+use embedded_hal_async::i2c::{
+    self, ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation, SevenBitAddress,
+};
+use person_sensor::{probe, PersonSensorBuilder, ProbeError};
+
+/// A bus with nothing connected to it.
+struct EmptyBus;
+
+#[derive(Debug, PartialEq)]
+struct Nack;
+
+impl i2c::Error for Nack {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+    }
+}
+
+impl ErrorType for EmptyBus {
+    type Error = Nack;
+}
+
+impl I2c<SevenBitAddress> for EmptyBus {
+    async fn transaction(
+        &mut self,
+        _address: SevenBitAddress,
+        _operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        Err(Nack)
+    }
+}
+
+#[tokio::test]
+async fn probe_finds_the_sensor() {
+    let mut i2c = MockPersonSensorBus::new(1, ONE_FACE).with_probe_payload(ONE_FACE);
+    assert_eq!(probe(&mut i2c).await, Ok(()));
+    // Probing doesn't configure the sensor
+    assert!(i2c.writes().is_empty());
+}
+
+#[tokio::test]
+async fn probe_reports_missing_sensor() {
+    assert_eq!(probe(&mut EmptyBus).await, Err(ProbeError::NotDetected));
+}
+
+#[tokio::test]
+async fn probe_rejects_bad_checksum() {
+    let mut i2c = MockPersonSensorBus::new(1, NO_FACES).with_probe_payload(BAD_CHECKSUM);
+    assert_eq!(probe(&mut i2c).await, Err(ProbeError::InvalidResponse));
+}
+
+#[tokio::test]
+async fn probe_rejects_bad_header() {
+    // A frame from another device could happen to have a valid checksum
+    let mut frame = [0u8; 39];
+    let checksum = crc16::State::<crc16::MCRF4XX>::calculate(&frame[..37]);
+    frame[37..].copy_from_slice(&checksum.to_le_bytes());
+
+    let mut i2c = MockPersonSensorBus::new(1, NO_FACES).with_probe_payload(frame);
+    assert_eq!(probe(&mut i2c).await, Err(ProbeError::InvalidResponse));
+}
+
+#[tokio::test]
+async fn build_fails_without_sensor() {
+    let result = PersonSensorBuilder::new_continuous(EmptyBus, true)
+        .build()
+        .await;
+    assert_eq!(result.err(), Some(ProbeError::NotDetected));
+
+    let result = PersonSensorBuilder::new_standby(EmptyBus, true)
+        .build()
+        .await;
+    assert_eq!(result.err(), Some(ProbeError::NotDetected));
+}
+
+#[tokio::test]
+async fn build_fails_on_invalid_response() {
+    let i2c = MockPersonSensorBus::new(1, NO_FACES).with_probe_payload(BAD_CHECKSUM);
+    let result = PersonSensorBuilder::new_standby(i2c, true).build().await;
+    assert_eq!(result.err(), Some(ProbeError::InvalidResponse));
+}
@@ -0,0 +1,122 @@
+mod common;
+use common::{payload, MockPersonSensorBus, TWO_FACES};
+use person_sensor::{PersonID, PersonSensorBuilder, Recognition};
+
+async fn decode(faces: &[[u8; 8]]) -> Vec<Recognition> {
+    let payload = payload(faces);
+    let i2c = MockPersonSensorBus::new(1, payload);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, true)
+        .build()
+        .await
+        .unwrap();
+    let detections = person_sensor.get_detections().await.unwrap();
+    detections.iter().map(|face| face.recognition).collect()
+}
+
+#[tokio::test]
+async fn identified() {
+    let recognitions = decode(&[[0x63, 10, 10, 60, 60, 85, 5, 1]]).await;
+    assert_eq!(
+        recognitions,
+        [Recognition::Identified {
+            id: PersonID::new(5).unwrap(),
+            confidence: 85,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn zero_confidence_is_unknown() {
+    let recognitions = decode(&[[0x63, 10, 10, 60, 60, 0, 0, 1]]).await;
+    assert_eq!(recognitions, [Recognition::Unknown]);
+}
+
+#[tokio::test]
+async fn negative_confidence_is_unknown() {
+    // -1 and -128
+    let recognitions = decode(&[[0x63, 10, 10, 60, 60, 0xff, 3, 1]]).await;
+    assert_eq!(recognitions, [Recognition::Unknown]);
+    let recognitions = decode(&[[0x63, 10, 10, 60, 60, 0x80, 200, 1]]).await;
+    assert_eq!(recognitions, [Recognition::Unknown]);
+}
+
+#[tokio::test]
+async fn out_of_range_id_is_unknown() {
+    let recognitions = decode(&[[0x63, 10, 10, 60, 60, 90, 8, 1]]).await;
+    assert_eq!(recognitions, [Recognition::Unknown]);
+    let recognitions = decode(&[[0x63, 10, 10, 60, 60, 90, 0xff, 1]]).await;
+    assert_eq!(recognitions, [Recognition::Unknown]);
+}
+
+#[tokio::test]
+async fn smaller_faces_are_not_largest() {
+    let recognitions = decode(&[
+        [0x63, 10, 10, 30, 30, 0xf0, 0, 1],
+        [0x63, 100, 100, 200, 200, 0, 0, 1],
+        [0x63, 50, 50, 60, 60, 0, 0, 0],
+    ])
+    .await;
+    assert_eq!(
+        recognitions,
+        [
+            Recognition::NotLargestFace,
+            Recognition::Unknown,
+            Recognition::NotLargestFace,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn first_of_equal_faces_is_largest() {
+    let recognitions = decode(&[
+        [0x63, 10, 10, 30, 30, 0, 0, 1],
+        [0x63, 50, 50, 70, 70, 0, 0, 1],
+    ])
+    .await;
+    assert_eq!(
+        recognitions,
+        [Recognition::Unknown, Recognition::NotLargestFace]
+    );
+}
+
+#[tokio::test]
+async fn smaller_faces_can_be_identified() {
+    // Captured from a sensor: both faces were identified as the same calibrated person
+    let i2c = MockPersonSensorBus::new(1, TWO_FACES);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, true)
+        .build()
+        .await
+        .unwrap();
+    let detections = person_sensor.get_detections().await.unwrap();
+
+    let id = PersonID::new(0).unwrap();
+    assert!(detections[1].area() < detections[0].area());
+    assert_eq!(
+        detections[0].recognition,
+        Recognition::Identified { id, confidence: 78 }
+    );
+    assert_eq!(
+        detections[1].recognition,
+        Recognition::Identified { id, confidence: 56 }
+    );
+}
+
+#[test]
+fn from_raw() {
+    let id = PersonID::new(7).unwrap();
+    assert_eq!(
+        Recognition::from_raw(7, 1, false),
+        Recognition::Identified { id, confidence: 1 }
+    );
+    assert_eq!(
+        Recognition::from_raw(7, 100, true).id(),
+        Some(PersonID::new(7).unwrap())
+    );
+    assert_eq!(
+        Recognition::from_raw(7, 0, false),
+        Recognition::NotLargestFace
+    );
+    assert_eq!(Recognition::from_raw(7, -5, true), Recognition::Unknown);
+    assert_eq!(Recognition::from_raw(9, 50, false), Recognition::Unknown);
+    assert_eq!(Recognition::from_raw(9, 50, false).id(), None);
+}
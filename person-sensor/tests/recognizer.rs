@@ -0,0 +1,121 @@
+use person_sensor::{
+    recognizer::{RecognitionEvent, Recognizer, RecognizerConfig},
+    Face, PersonID, Recognition,
+};
+
+fn face(id: Option<u8>, id_confidence: i8) -> Face {
+    Face {
+        box_confidence: 99,
+        box_left: 100,
+        box_top: 100,
+        box_right: 150,
+        box_bottom: 150,
+        recognition: Recognition::from_raw(id.unwrap_or(0xff), id_confidence, true),
+        is_facing: true,
+    }
+}
+
+const CONFIG: RecognizerConfig = RecognizerConfig {
+    accept_score: 60,
+    reject_score: 30,
+};
+
+#[test]
+fn recognizes_after_sustained_confidence() {
+    let mut recognizer = Recognizer::<4>::new(CONFIG);
+    let alice = PersonID::new(1).unwrap();
+
+    // A single confident frame is not enough to fill the window
+    assert_eq!(recognizer.update(&[face(Some(1), 90)]), None);
+    assert_eq!(recognizer.update(&[face(Some(1), 90)]), None);
+    assert_eq!(
+        recognizer.update(&[face(Some(1), 90)]),
+        Some(RecognitionEvent::Recognized(alice))
+    );
+    assert_eq!(recognizer.current(), Some(alice));
+    assert_eq!(recognizer.score(alice), 67);
+}
+
+#[test]
+fn hysteresis_prevents_flicker() {
+    let mut recognizer = Recognizer::<4>::new(CONFIG);
+    let alice = PersonID::new(1).unwrap();
+
+    for _ in 0..4 {
+        recognizer.update(&[face(Some(1), 80)]);
+    }
+    assert_eq!(recognizer.current(), Some(alice));
+
+    // Alternating recognized / unknown frames keep the score between the thresholds
+    for _ in 0..10 {
+        assert_eq!(recognizer.update(&[face(None, 0)]), None);
+        assert_eq!(recognizer.update(&[face(Some(1), 80)]), None);
+    }
+    assert_eq!(recognizer.score(alice), 40);
+    assert_eq!(recognizer.current(), Some(alice));
+}
+
+#[test]
+fn lost_when_score_drops() {
+    let mut recognizer = Recognizer::<4>::new(CONFIG);
+    let alice = PersonID::new(1).unwrap();
+
+    for _ in 0..4 {
+        recognizer.update(&[face(Some(1), 80)]);
+    }
+
+    assert_eq!(recognizer.update(&[]), None);
+    assert_eq!(recognizer.update(&[]), None);
+    assert_eq!(recognizer.update(&[]), Some(RecognitionEvent::Lost(alice)));
+    assert_eq!(recognizer.current(), None);
+}
+
+#[test]
+fn switching_people_loses_first() {
+    let mut recognizer = Recognizer::<2>::new(CONFIG);
+    let alice = PersonID::new(1).unwrap();
+    let bob = PersonID::new(2).unwrap();
+
+    recognizer.update(&[face(Some(1), 90)]);
+    recognizer.update(&[face(Some(1), 90)]);
+    assert_eq!(recognizer.current(), Some(alice));
+
+    assert_eq!(recognizer.update(&[face(Some(2), 90)]), None);
+    assert_eq!(
+        recognizer.update(&[face(Some(2), 90)]),
+        Some(RecognitionEvent::Lost(alice))
+    );
+    assert_eq!(
+        recognizer.update(&[face(Some(2), 90)]),
+        Some(RecognitionEvent::Recognized(bob))
+    );
+}
+
+#[test]
+fn ignores_unrecognized_faces() {
+    let mut recognizer = Recognizer::<2>::new(CONFIG);
+
+    for _ in 0..4 {
+        assert_eq!(
+            recognizer.update(&[face(Some(1), -20), face(None, 0)]),
+            None
+        );
+    }
+    assert_eq!(recognizer.current(), None);
+}
+
+#[test]
+fn uses_most_confident_face() {
+    let mut recognizer = Recognizer::<2>::new(CONFIG);
+    let bob = PersonID::new(2).unwrap();
+
+    recognizer.update(&[face(Some(1), 40), face(Some(2), 90)]);
+    assert_eq!(
+        recognizer.update(&[face(Some(1), 40), face(Some(2), 90)]),
+        Some(RecognitionEvent::Recognized(bob))
+    );
+
+    recognizer.reset();
+    assert_eq!(recognizer.current(), None);
+    assert_eq!(recognizer.score(bob), 0);
+}
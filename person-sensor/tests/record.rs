@@ -0,0 +1,149 @@
+mod common;
+use common::{MockClock, MockPersonSensorBus, NO_FACES, ONE_FACE, TWO_FACES};
+use embedded_hal_async::i2c::{ErrorKind, I2c};
+use person_sensor::{
+    record::{Record, Recorder, Records, Replay, ReplayError},
+    PersonSensorBuilder, ProbeError, ReadError,
+};
+
+/// Records building a continuous sensor and reading each payload once, 100ms apart.
+async fn record(payloads: &[[u8; 39]]) -> Vec<u8> {
+    let clock = MockClock::default();
+    clock.set_ms(5_000);
+    let mut recording = Vec::new();
+    let i2c = Recorder::new(
+        MockPersonSensorBus::with_payloads(1, payloads),
+        || clock.now_ms(),
+        |bytes: &[u8]| recording.extend_from_slice(bytes),
+    );
+
+    let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, true)
+        .build()
+        .await
+        .unwrap();
+    for _ in payloads {
+        clock.advance_ms(100);
+        person_sensor.get_detections().await.unwrap();
+    }
+    drop(person_sensor);
+    recording
+}
+
+#[tokio::test]
+async fn records_timestamped_transactions() {
+    let recording = record(&[ONE_FACE, NO_FACES]).await;
+
+    let records: Vec<_> = Records::new(&recording)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        records,
+        [
+            // The builder's probe
+            Record::Read {
+                address: 0x62,
+                timestamp_ms: 0,
+                data: &NO_FACES,
+            },
+            Record::Write {
+                address: 0x62,
+                timestamp_ms: 0,
+                data: &[0x01, 0x01],
+            },
+            Record::Write {
+                address: 0x62,
+                timestamp_ms: 0,
+                data: &[0x02, 0x01],
+            },
+            Record::Read {
+                address: 0x62,
+                timestamp_ms: 100,
+                data: &ONE_FACE,
+            },
+            Record::Read {
+                address: 0x62,
+                timestamp_ms: 200,
+                data: &NO_FACES,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn replays_into_the_driver() {
+    let recording = record(&[ONE_FACE, TWO_FACES, NO_FACES]).await;
+    let replay = Replay::new(&recording).unwrap();
+
+    let mut person_sensor = PersonSensorBuilder::new_continuous(replay.bus(), true)
+        .build()
+        .await
+        .unwrap();
+    let mut counts = Vec::new();
+    let mut timestamps = Vec::new();
+    for _ in 0..3 {
+        counts.push(person_sensor.get_detections().await.unwrap().len());
+        timestamps.push(replay.now_ms());
+    }
+    assert_eq!(counts, [1, 2, 0]);
+    assert_eq!(timestamps, [100, 200, 300]);
+    assert!(replay.is_finished());
+
+    assert_eq!(
+        person_sensor.get_detections().await,
+        Err(ReadError::I2CError(ReplayError::Finished))
+    );
+}
+
+#[tokio::test]
+async fn replay_detects_divergence() {
+    let recording = record(&[ONE_FACE]).await;
+    let replay = Replay::new(&recording).unwrap();
+
+    // The recording was made with the ID model enabled
+    let result = PersonSensorBuilder::new_continuous(replay.bus(), false)
+        .build()
+        .await;
+    assert_eq!(
+        result.err(),
+        Some(ProbeError::I2CError(ReplayError::Diverged))
+    );
+}
+
+#[tokio::test]
+async fn replays_failed_transactions() {
+    let mut recording = Vec::new();
+    let mut i2c = Recorder::new(
+        MockPersonSensorBus::new(1, NO_FACES),
+        || 0,
+        |bytes: &[u8]| recording.extend_from_slice(bytes),
+    );
+    assert!(i2c.write(0x30, &[0x01, 0x00]).await.is_err());
+    drop(i2c);
+
+    let replay = Replay::new(&recording).unwrap();
+    let mut bus = replay.bus();
+    assert_eq!(
+        bus.write(0x30, &[0x01, 0x00]).await,
+        Err(ReplayError::Recorded(ErrorKind::Other))
+    );
+}
+
+#[test]
+fn rejects_invalid_recordings() {
+    assert_eq!(
+        Replay::new(b"PSR\x02").err(),
+        Some(ReplayError::InvalidRecording)
+    );
+    assert_eq!(
+        Replay::new(b"XYZ\x01").err(),
+        Some(ReplayError::InvalidRecording)
+    );
+    assert!(Replay::new(&[]).unwrap().is_finished());
+
+    // A record that claims more data than the recording contains
+    let truncated = b"PSR\x01\x01\x62\x00\x00\x00\x00\x27\x00\x00";
+    let mut records = Records::new(truncated).unwrap();
+    assert_eq!(records.next(), Some(Err(ReplayError::InvalidRecording)));
+    assert_eq!(records.next(), None);
+}
@@ -0,0 +1,153 @@
+mod common;
+use common::{MockPersonSensorBus, NO_FACES};
+use person_sensor::{
+    registry::{IdentityRegistry, RegistryError, MAX_IDS},
+    PersonID, PersonSensorBuilder,
+};
+
+fn id(id: u8) -> PersonID {
+    PersonID::new(id).unwrap()
+}
+
+#[test]
+fn allocates_lowest_free_id() {
+    let mut registry = IdentityRegistry::<16>::new();
+    assert!(registry.is_empty());
+
+    assert_eq!(registry.allocate("alice"), Ok(id(0)));
+    assert_eq!(registry.allocate("bob"), Ok(id(1)));
+    assert_eq!(registry.release(id(0)).as_deref(), Some("alice"));
+    assert_eq!(registry.allocate("carol"), Ok(id(0)));
+
+    assert_eq!(registry.len(), 2);
+    assert_eq!(registry.get(id(0)), Some("carol"));
+    assert_eq!(registry.find("bob"), Some(id(1)));
+    assert_eq!(registry.find("alice"), None);
+    assert_eq!(registry.next_free(), Some(id(2)));
+}
+
+#[test]
+fn full_registry() {
+    let mut registry = IdentityRegistry::<4>::new();
+    for _ in 0..MAX_IDS {
+        registry.allocate("x").unwrap();
+    }
+    assert_eq!(registry.allocate("y"), Err(RegistryError::Full));
+    assert_eq!(registry.free_ids().count(), 0);
+}
+
+#[test]
+fn label_too_long() {
+    let mut registry = IdentityRegistry::<4>::new();
+    assert_eq!(
+        registry.allocate("mallory"),
+        Err(RegistryError::LabelTooLong)
+    );
+    assert!(registry.is_free(id(0)));
+}
+
+#[test]
+fn out_of_range_id() {
+    let mut registry = IdentityRegistry::<4>::new();
+    let invalid = PersonID::new_unchecked(8);
+
+    assert_eq!(registry.assign(invalid, "x"), Err(RegistryError::InvalidId));
+    assert_eq!(registry.get(invalid), None);
+    assert_eq!(registry.release(invalid), None);
+    assert!(!registry.is_free(invalid));
+    assert!(registry.is_empty());
+}
+
+#[test]
+fn serialization_round_trip() {
+    let mut registry = IdentityRegistry::<16>::new();
+    registry.assign(id(1), "alice").unwrap();
+    registry.assign(id(6), "bob").unwrap();
+
+    let mut buffer = [0u8; 64];
+    let len = registry.to_bytes(&mut buffer).unwrap();
+    assert_eq!(len, registry.serialized_len());
+    assert_eq!(
+        &buffer[..len],
+        &[
+            1,
+            0,
+            0b0100_0010,
+            5,
+            b'a',
+            b'l',
+            b'i',
+            b'c',
+            b'e',
+            3,
+            b'b',
+            b'o',
+            b'b'
+        ]
+    );
+
+    let decoded = IdentityRegistry::<16>::from_bytes(&buffer[..len]).unwrap();
+    assert_eq!(decoded, registry);
+}
+
+#[test]
+fn serialization_errors() {
+    let mut registry = IdentityRegistry::<16>::new();
+    registry.allocate("alice").unwrap();
+
+    let mut buffer = [0u8; 4];
+    assert_eq!(
+        registry.to_bytes(&mut buffer),
+        Err(RegistryError::BufferTooSmall)
+    );
+
+    // Unsupported version
+    assert_eq!(
+        IdentityRegistry::<16>::from_bytes(&[2, 0, 0]),
+        Err(RegistryError::InvalidData)
+    );
+    // Truncated label
+    assert_eq!(
+        IdentityRegistry::<16>::from_bytes(&[1, 0, 1, 5, b'a']),
+        Err(RegistryError::InvalidData)
+    );
+    // Trailing data
+    assert_eq!(
+        IdentityRegistry::<16>::from_bytes(&[1, 0, 0, 0]),
+        Err(RegistryError::InvalidData)
+    );
+    // Label longer than the registry capacity
+    assert_eq!(
+        IdentityRegistry::<2>::from_bytes(&[1, 0, 1, 3, b'b', b'o', b'b']),
+        Err(RegistryError::LabelTooLong)
+    );
+}
+
+#[tokio::test]
+async fn erase_and_persist_update_sensor() {
+    let mut i2c = MockPersonSensorBus::new(0, NO_FACES);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(&mut i2c, true)
+        .build()
+        .await
+        .unwrap();
+
+    let mut registry = IdentityRegistry::<16>::new();
+    registry.allocate("alice").unwrap();
+    registry
+        .set_persist(&mut person_sensor, true)
+        .await
+        .unwrap();
+    registry.erase(&mut person_sensor).await.unwrap();
+
+    assert!(registry.is_empty());
+    assert!(registry.persist());
+
+    let restored = IdentityRegistry::<16>::from_bytes(&[1, 1, 0]).unwrap();
+    restored.sync(&mut person_sensor).await.unwrap();
+
+    let writes = i2c.writes();
+    assert_eq!(
+        writes[writes.len() - 3..],
+        [vec![0x05, 1], vec![0x06, 0], vec![0x05, 1]]
+    );
+}
@@ -0,0 +1,100 @@
+mod common;
+use common::{face, MockClock, MockDelay, MockPersonSensorBus};
+use person_sensor::{encode_frame, Face, PersonSensorBuilder, SensorRevision};
+
+/// Frames that change every `reads_per_frame` reads, as when somebody moves in front of the
+/// sensor.
+fn moving_frames(reads_per_frame: usize) -> Vec<[u8; 39]> {
+    (0..40u8)
+        .flat_map(|i| {
+            let frame = encode_frame(&[Face {
+                box_left: i,
+                ..face()
+            }]);
+            std::iter::repeat_n(frame, reads_per_frame)
+        })
+        .collect()
+}
+
+async fn detect(payloads: &[[u8; 39]]) -> Option<SensorRevision> {
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+    let i2c = MockPersonSensorBus::with_payloads(1, payloads);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, true)
+        .with_revision(SensorRevision::V1)
+        .build()
+        .await
+        .unwrap();
+
+    let revision = person_sensor
+        .detect_revision(&mut delay, 3_000)
+        .await
+        .unwrap();
+    assert_eq!(
+        person_sensor.revision(),
+        revision.unwrap_or(SensorRevision::V1)
+    );
+    assert!(clock.now_ms() <= 3_000);
+    revision
+}
+
+#[tokio::test]
+async fn detects_v1_frame_rate() {
+    // Reads are 10ms apart
+    assert_eq!(detect(&moving_frames(20)).await, Some(SensorRevision::V1));
+}
+
+#[tokio::test]
+async fn detects_v2_frame_rate() {
+    assert_eq!(detect(&moving_frames(10)).await, Some(SensorRevision::V2));
+}
+
+#[tokio::test]
+async fn static_scene_is_not_detected() {
+    assert_eq!(detect(&[common::ONE_FACE]).await, None);
+}
+
+#[tokio::test]
+async fn revision_sets_capture_wait() {
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+    let mut person_sensor =
+        PersonSensorBuilder::new_standby(MockPersonSensorBus::new(0, common::ONE_FACE), true)
+            .with_revision(SensorRevision::V2)
+            .build()
+            .await
+            .unwrap();
+    assert_eq!(person_sensor.revision(), SensorRevision::V2);
+
+    person_sensor.capture_once(&mut delay).await.unwrap();
+    assert_eq!(clock.now_ms(), 100);
+}
+
+#[tokio::test]
+async fn unknown_revision_keeps_default_capture_wait() {
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+    let mut person_sensor =
+        PersonSensorBuilder::new_standby(MockPersonSensorBus::new(0, common::ONE_FACE), true)
+            .build()
+            .await
+            .unwrap();
+    assert_eq!(person_sensor.revision(), SensorRevision::Unknown);
+
+    person_sensor.capture_once(&mut delay).await.unwrap();
+    assert_eq!(clock.now_ms(), 200);
+}
+
+#[test]
+fn revision_defaults() {
+    assert_eq!(SensorRevision::V1.frame_interval_ms(), 200);
+    assert_eq!(SensorRevision::V2.frame_interval_ms(), 100);
+    assert_eq!(
+        SensorRevision::from_frame_interval_ms(150),
+        SensorRevision::V1
+    );
+    assert_eq!(
+        SensorRevision::from_frame_interval_ms(149),
+        SensorRevision::V2
+    );
+}
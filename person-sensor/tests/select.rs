@@ -0,0 +1,146 @@
+use person_sensor::{
+    select::{PrimaryTarget, TargetConfig, TargetPriority},
+    tracker::TrackerConfig,
+    Face, PersonID, Recognition, SelectFaces,
+};
+
+fn face(left: u8, top: u8, size: u8, box_confidence: u8) -> Face {
+    Face {
+        box_confidence,
+        box_left: left,
+        box_top: top,
+        box_right: left + size,
+        box_bottom: top + size,
+        recognition: Recognition::Unknown,
+        is_facing: true,
+    }
+}
+
+#[test]
+fn selects_faces() {
+    let faces = [
+        face(0, 0, 80, 70),
+        face(110, 110, 40, 60),
+        face(200, 10, 50, 95),
+    ];
+    assert_eq!(faces.largest(), Some(&faces[0]));
+    assert_eq!(faces.most_centered(), Some(&faces[1]));
+    assert_eq!(faces.most_confident(), Some(&faces[2]));
+
+    let none: [Face; 0] = [];
+    assert_eq!(none.largest(), None);
+}
+
+#[test]
+fn first_of_equal_faces_is_selected() {
+    let faces = [face(0, 0, 50, 90), face(100, 0, 50, 90)];
+    assert_eq!(faces.largest(), Some(&faces[0]));
+    assert_eq!(faces.most_confident(), Some(&faces[0]));
+}
+
+#[test]
+fn filters_faces() {
+    let alice = PersonID::new(1).unwrap();
+    let mut faces = heapless::Vec::<Face, 4>::new();
+    faces.push(face(0, 0, 50, 90)).unwrap();
+    faces.push(face(100, 0, 50, 90)).unwrap();
+    faces[0].is_facing = false;
+    faces[1].recognition = Recognition::Identified {
+        id: alice,
+        confidence: 95,
+    };
+
+    assert_eq!(faces.facing().collect::<Vec<_>>(), [&faces[1]]);
+    assert_eq!(faces.identified().collect::<Vec<_>>(), [(alice, &faces[1])]);
+}
+
+const CONFIG: TargetConfig = TargetConfig {
+    priority: TargetPriority::Largest,
+    facing_only: false,
+    switch_margin_percent: 20,
+    tracker: TrackerConfig {
+        max_distance: 30,
+        max_missed_frames: 1,
+    },
+};
+
+#[test]
+fn target_sticks_to_similar_faces() {
+    let mut target = PrimaryTarget::<4>::new(CONFIG);
+    let first = target.update(&[face(20, 20, 50, 90)]).unwrap().id();
+
+    // Slightly larger isn't enough to take over
+    let target_id = target
+        .update(&[face(20, 20, 50, 90), face(150, 20, 52, 90)])
+        .unwrap()
+        .id();
+    assert_eq!(target_id, first);
+
+    // Much larger is
+    let target_id = target
+        .update(&[face(20, 20, 50, 90), face(150, 20, 70, 90)])
+        .unwrap()
+        .id();
+    assert_ne!(target_id, first);
+}
+
+#[test]
+fn target_survives_missed_frames() {
+    let mut target = PrimaryTarget::<4>::new(CONFIG);
+    let first = target.update(&[face(20, 20, 50, 90)]).unwrap().id();
+
+    let track = target.update(&[face(150, 20, 80, 90)]).unwrap();
+    assert_eq!(track.id(), first);
+    assert!(!track.is_visible());
+
+    // Once the target is lost, the best remaining face takes over
+    let track = target.update(&[face(150, 20, 80, 90)]).unwrap();
+    assert_ne!(track.id(), first);
+
+    target.update(&[]);
+    assert!(target.update(&[]).is_none());
+}
+
+#[test]
+fn centered_target_sticks() {
+    let mut target = PrimaryTarget::<4>::new(TargetConfig {
+        priority: TargetPriority::MostCentered,
+        ..CONFIG
+    });
+    let first = target.update(&[face(100, 100, 40, 90)]).unwrap().id();
+
+    // A face moving in from the side doesn't take over while it's further from the center
+    let target_id = target
+        .update(&[face(96, 100, 40, 90), face(140, 100, 40, 90)])
+        .unwrap()
+        .id();
+    assert_eq!(target_id, first);
+
+    // It does once it's much closer
+    let target_id = target
+        .update(&[face(84, 100, 40, 90), face(118, 100, 40, 90)])
+        .unwrap()
+        .id();
+    assert_ne!(target_id, first);
+}
+
+#[test]
+fn facing_only_target() {
+    let mut target = PrimaryTarget::<4>::new(TargetConfig {
+        facing_only: true,
+        ..CONFIG
+    });
+    let mut away = face(20, 20, 80, 90);
+    away.is_facing = false;
+    let first = target
+        .update(&[away.clone(), face(150, 20, 40, 90)])
+        .unwrap()
+        .id();
+
+    // The target looks away while somebody else is facing the sensor
+    let mut target_away = face(150, 20, 40, 90);
+    target_away.is_facing = false;
+    assert_eq!(target.update(&[target_away.clone()]).unwrap().id(), first);
+    let track = target.update(&[target_away, face(20, 20, 80, 90)]).unwrap();
+    assert_ne!(track.id(), first);
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "serde")]
+
+mod common;
+use common::{MockPersonSensorBus, TWO_FACES};
+use person_sensor::{
+    command::Command, zone::Polygon, BoundingBox, Detections, Face, PersonID, PersonSensorBuilder,
+    PersonSensorMode, Recognition,
+};
+
+fn face() -> Face {
+    Face {
+        box_confidence: 99,
+        box_left: 10,
+        box_top: 20,
+        box_right: 30,
+        box_bottom: 40,
+        recognition: Recognition::Identified {
+            id: PersonID::new(3).unwrap(),
+            confidence: 80,
+        },
+        is_facing: true,
+    }
+}
+
+#[test]
+fn face_round_trip() {
+    let face = face();
+
+    let bytes = postcard::to_allocvec(&face).unwrap();
+    assert_eq!(postcard::from_bytes::<Face>(&bytes).unwrap(), face);
+
+    let json = serde_json::to_string(&face).unwrap();
+    assert_eq!(serde_json::from_str::<Face>(&json).unwrap(), face);
+}
+
+#[test]
+fn person_id_is_validated() {
+    let id = PersonID::new(7).unwrap();
+    assert_eq!(serde_json::to_string(&id).unwrap(), "7");
+    assert_eq!(serde_json::from_str::<PersonID>("7").unwrap(), id);
+    assert!(serde_json::from_str::<PersonID>("8").is_err());
+
+    assert_eq!(postcard::to_allocvec(&id).unwrap(), [7]);
+    assert!(postcard::from_bytes::<PersonID>(&[8]).is_err());
+}
+
+#[test]
+fn recognition_is_validated() {
+    let recognition = face().recognition;
+    let json = serde_json::to_string(&recognition).unwrap();
+    assert_eq!(json, r#"{"Identified":{"id":3,"confidence":80}}"#);
+    assert_eq!(
+        serde_json::from_str::<Recognition>(&json).unwrap(),
+        recognition
+    );
+    assert_eq!(
+        serde_json::from_str::<Recognition>(r#""NotLargestFace""#).unwrap(),
+        Recognition::NotLargestFace
+    );
+    assert!(
+        serde_json::from_str::<Recognition>(r#"{"Identified":{"id":3,"confidence":0}}"#).is_err()
+    );
+    assert!(
+        serde_json::from_str::<Recognition>(r#"{"Identified":{"id":8,"confidence":80}}"#).is_err()
+    );
+
+    let bytes = postcard::to_allocvec(&recognition).unwrap();
+    assert_eq!(
+        postcard::from_bytes::<Recognition>(&bytes).unwrap(),
+        recognition
+    );
+    assert!(postcard::from_bytes::<Recognition>(&[2, 3, 0]).is_err());
+}
+
+#[test]
+fn polygon_is_validated() {
+    let polygon = Polygon::new(&[(0, 0), (100, 0), (50, 80)]).unwrap();
+    let json = serde_json::to_string(&polygon).unwrap();
+    assert_eq!(json, "[[0,0],[100,0],[50,80]]");
+    assert_eq!(serde_json::from_str::<Polygon>(&json).unwrap(), polygon);
+    assert!(serde_json::from_str::<Polygon>("[[0,0],[100,0]]").is_err());
+
+    let bytes = postcard::to_allocvec(&polygon).unwrap();
+    assert_eq!(postcard::from_bytes::<Polygon>(&bytes).unwrap(), polygon);
+    assert!(postcard::from_bytes::<Polygon>(&[2, 0, 0, 100, 0]).is_err());
+}
+
+#[test]
+fn bounding_box_round_trip() {
+    let bounding_box = face().bounding_box();
+    assert_eq!(
+        serde_json::to_string(&bounding_box).unwrap(),
+        r#"{"left":10,"top":20,"right":30,"bottom":40}"#
+    );
+
+    let bytes = postcard::to_allocvec(&bounding_box).unwrap();
+    assert_eq!(bytes, [10, 20, 30, 40]);
+    assert_eq!(
+        postcard::from_bytes::<BoundingBox>(&bytes).unwrap(),
+        bounding_box
+    );
+}
+
+#[tokio::test]
+async fn frame_round_trip() {
+    let i2c = MockPersonSensorBus::new(1, TWO_FACES);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, true)
+        .build()
+        .await
+        .unwrap();
+    let detections: Detections = person_sensor.get_detections().await.unwrap();
+
+    let bytes = postcard::to_allocvec(&detections).unwrap();
+    assert_eq!(
+        postcard::from_bytes::<Detections>(&bytes).unwrap(),
+        detections
+    );
+
+    let json = serde_json::to_string(&detections).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Detections>(&json).unwrap(),
+        detections
+    );
+}
+
+#[test]
+fn too_many_faces_rejected() {
+    let json = serde_json::to_string(&vec![face(); 5]).unwrap();
+    assert!(serde_json::from_str::<Detections>(&json).is_err());
+}
+
+#[test]
+fn commands_round_trip() {
+    let commands = [
+        Command::SetMode(PersonSensorMode::Standby),
+        Command::EnableIdModel(false),
+        Command::LabelNextId(PersonID::new(4).unwrap()),
+        Command::SetPersistIds(true),
+        Command::EraseIds,
+        Command::SetIndicator(false),
+        Command::CaptureOnce,
+    ];
+
+    for command in commands {
+        let bytes = postcard::to_allocvec(&command).unwrap();
+        assert_eq!(postcard::from_bytes::<Command>(&bytes).unwrap(), command);
+    }
+    assert_eq!(
+        serde_json::to_string(&commands[2]).unwrap(),
+        r#"{"LabelNextId":4}"#
+    );
+    // IDs are validated when received
+    assert!(serde_json::from_str::<Command>(r#"{"LabelNextId":9}"#).is_err());
+}
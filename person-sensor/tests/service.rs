@@ -0,0 +1,190 @@
+#![cfg(feature = "embassy")]
+
+mod common;
+use std::time::Duration;
+
+use common::{MockInterrupt, MockPersonSensorBus, NoopDelay, NO_FACES, ONE_FACE, TWO_FACES};
+use embassy_sync::{
+    blocking_mutex::raw::NoopRawMutex, channel::Channel, pubsub::PubSubChannel, watch::Watch,
+};
+use person_sensor::{
+    command::Command, power::ManagedSensor, service::SensorService, Detections, PersonID,
+    PersonSensorBuilder, PersonSensorMode,
+};
+
+type Commands = Channel<NoopRawMutex, Command, 4>;
+
+#[tokio::test]
+async fn publishes_only_changes() {
+    let commands = Commands::new();
+    let i2c = MockPersonSensorBus::with_payloads(1, &[ONE_FACE, ONE_FACE, TWO_FACES]);
+    let sensor = PersonSensorBuilder::new_continuous(i2c, true)
+        .build()
+        .await
+        .unwrap();
+
+    let mut published = Vec::new();
+    let mut service = SensorService::new(sensor, commands.dyn_receiver(), |faces: &Detections| {
+        published.push(faces.len())
+    });
+    for _ in 0..3 {
+        service.step(&mut NoopDelay).await.unwrap();
+    }
+    drop(service);
+    assert_eq!(published, [1, 2]);
+}
+
+#[tokio::test]
+async fn fans_out_through_watch() {
+    let commands = Commands::new();
+    let watch = Watch::<NoopRawMutex, Detections, 2>::new();
+    let mut display = watch.receiver().unwrap();
+    let mut logger = watch.receiver().unwrap();
+    let sensor = PersonSensorBuilder::new_continuous(MockPersonSensorBus::new(1, ONE_FACE), true)
+        .build()
+        .await
+        .unwrap();
+
+    let mut service = SensorService::new(sensor, commands.dyn_receiver(), watch.sender());
+    service.step(&mut NoopDelay).await.unwrap();
+    assert_eq!(display.try_changed().unwrap().len(), 1);
+    assert_eq!(logger.try_changed().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn fans_out_through_pubsub() {
+    let commands = Commands::new();
+    let channel = PubSubChannel::<NoopRawMutex, Detections, 4, 2, 1>::new();
+    let mut display = channel.subscriber().unwrap();
+    let mut logger = channel.subscriber().unwrap();
+    let i2c = MockPersonSensorBus::with_payloads(1, &[ONE_FACE, NO_FACES]);
+    let sensor = PersonSensorBuilder::new_continuous(i2c, true)
+        .build()
+        .await
+        .unwrap();
+
+    let mut service = SensorService::new(
+        sensor,
+        commands.dyn_receiver(),
+        channel.immediate_publisher(),
+    );
+    service.step(&mut NoopDelay).await.unwrap();
+    service.step(&mut NoopDelay).await.unwrap();
+    for subscriber in [&mut display, &mut logger] {
+        assert_eq!(subscriber.try_next_message_pure().unwrap().len(), 1);
+        assert!(subscriber.try_next_message_pure().unwrap().is_empty());
+    }
+}
+
+#[tokio::test]
+async fn executes_commands() {
+    let commands = Commands::new();
+    let sensor = PersonSensorBuilder::new_continuous(MockPersonSensorBus::new(1, ONE_FACE), true)
+        .build()
+        .await
+        .unwrap();
+
+    let mut published = 0;
+    let mut service = SensorService::new(sensor, commands.dyn_receiver(), |_: &Detections| {
+        published += 1
+    });
+    commands.send(Command::SetIndicator(false)).await;
+    commands
+        .send(Command::SetMode(PersonSensorMode::Standby))
+        .await;
+    commands.send(Command::CaptureOnce).await;
+    commands
+        .send(Command::LabelNextId(PersonID::new(2).unwrap()))
+        .await;
+    for _ in 0..4 {
+        service.step(&mut NoopDelay).await.unwrap();
+    }
+    assert_eq!(service.mode(), PersonSensorMode::Standby);
+
+    let ManagedSensor::Standby(sensor) = service.release() else {
+        panic!("Expected the sensor in standby");
+    };
+    let (i2c, ()) = sensor.release();
+    assert_eq!(
+        i2c.writes()[2..],
+        [[0x07, 0x00], [0x01, 0x00], [0x03, 0x00], [0x04, 0x02]]
+    );
+    assert_eq!(published, 1);
+}
+
+#[tokio::test]
+async fn capture_publishes_unchanged_detections() {
+    let commands = Commands::new();
+    let sensor = PersonSensorBuilder::new_continuous(MockPersonSensorBus::new(1, ONE_FACE), true)
+        .build()
+        .await
+        .unwrap();
+
+    let mut published = 0;
+    let mut service = SensorService::new(sensor, commands.dyn_receiver(), |_: &Detections| {
+        published += 1
+    });
+    service.step(&mut NoopDelay).await.unwrap();
+    service.step(&mut NoopDelay).await.unwrap();
+    service
+        .execute(Command::CaptureOnce, &mut NoopDelay)
+        .await
+        .unwrap();
+    drop(service);
+    assert_eq!(published, 2);
+}
+
+#[tokio::test]
+async fn standby_waits_for_commands() {
+    let commands = Commands::new();
+    let sensor = PersonSensorBuilder::new_standby(MockPersonSensorBus::new(1, NO_FACES), true)
+        .build()
+        .await
+        .unwrap();
+
+    let mut service =
+        SensorService::from_standby(sensor, commands.dyn_receiver(), |_: &Detections| {});
+    let mut delay = NoopDelay;
+    let step = service.step(&mut delay);
+    assert!(tokio::time::timeout(Duration::from_millis(10), step)
+        .await
+        .is_err());
+
+    commands
+        .send(Command::SetMode(PersonSensorMode::Continuous))
+        .await;
+    service.step(&mut NoopDelay).await.unwrap();
+    assert_eq!(service.mode(), PersonSensorMode::Continuous);
+}
+
+#[tokio::test]
+async fn interrupt_pauses_polling_while_nobody_is_in_view() {
+    let commands = Commands::new();
+    let sensor = PersonSensorBuilder::new_continuous(MockPersonSensorBus::new(1, NO_FACES), true)
+        .with_interrupt(MockInterrupt {
+            high: false,
+            rising_edge: false,
+        })
+        .build()
+        .await
+        .unwrap();
+
+    let mut published = 0;
+    let mut service = SensorService::new(sensor, commands.dyn_receiver(), |_: &Detections| {
+        published += 1
+    });
+    // Nothing has been read yet, so the sensor is polled
+    service.step_with_interrupt(&mut NoopDelay).await.unwrap();
+
+    let mut delay = NoopDelay;
+    let step = service.step_with_interrupt(&mut delay);
+    assert!(tokio::time::timeout(Duration::from_millis(10), step)
+        .await
+        .is_err());
+
+    // Commands are still handled
+    commands.send(Command::SetIndicator(true)).await;
+    service.step_with_interrupt(&mut NoopDelay).await.unwrap();
+    drop(service);
+    assert_eq!(published, 1);
+}
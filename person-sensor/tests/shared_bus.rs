@@ -0,0 +1,91 @@
+mod common;
+use core::cell::RefCell;
+
+use common::{MockPersonSensorBus, NO_FACES, ONE_FACE};
+use embassy_embedded_hal::{adapter::BlockingAsync, shared_bus::asynch::i2c::I2cDevice};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, mutex::Mutex};
+use embedded_hal_async::i2c::I2c;
+use embedded_hal_bus::i2c::{CriticalSectionDevice, RefCellDevice};
+use person_sensor::PersonSensorBuilder;
+
+#[tokio::test]
+async fn borrowed_bus() {
+    let mut i2c = MockPersonSensorBus::new(1, ONE_FACE);
+
+    {
+        let mut person_sensor = PersonSensorBuilder::new_continuous(&mut i2c, true)
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(person_sensor.get_detections().await.unwrap().len(), 1);
+    }
+
+    // The bus is usable again once the driver is gone
+    assert_eq!(i2c.writes(), [[0x01, 0x01], [0x02, 0x01]]);
+}
+
+#[tokio::test]
+async fn released_bus() {
+    let i2c = MockPersonSensorBus::new(1, NO_FACES);
+
+    let person_sensor = PersonSensorBuilder::new_standby(i2c, true)
+        .build()
+        .await
+        .unwrap();
+    let (i2c, ()) = person_sensor.release();
+    assert_eq!(i2c.mode(), 0);
+}
+
+#[tokio::test]
+async fn embassy_i2c_device() {
+    let bus = Mutex::<NoopRawMutex, _>::new(MockPersonSensorBus::new(1, ONE_FACE));
+
+    let mut person_sensor = PersonSensorBuilder::new_continuous(I2cDevice::new(&bus), true)
+        .build()
+        .await
+        .unwrap();
+    // Another device on the same bus, using it between sensor reads
+    let mut other = I2cDevice::new(&bus);
+
+    assert_eq!(person_sensor.get_detections().await.unwrap().len(), 1);
+    other.write(0x62, &[0x07, 0x00]).await.unwrap();
+    assert_eq!(person_sensor.get_detections().await.unwrap().len(), 1);
+
+    assert_eq!(bus.lock().await.writes().len(), 3);
+}
+
+#[tokio::test]
+async fn refcell_device() {
+    let bus = RefCell::new(MockPersonSensorBus::new(1, ONE_FACE));
+
+    let i2c = BlockingAsync::new(RefCellDevice::new(&bus));
+    let mut person_sensor = PersonSensorBuilder::new_continuous(i2c, false)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(person_sensor.get_detections().await.unwrap().len(), 1);
+
+    // The bus isn't held between transactions
+    assert_eq!(bus.borrow().writes(), [[0x01, 0x01], [0x02, 0x00]]);
+}
+
+#[tokio::test]
+async fn critical_section_device() {
+    let bus = critical_section::Mutex::new(RefCell::new(MockPersonSensorBus::new(1, ONE_FACE)));
+
+    let i2c = BlockingAsync::new(CriticalSectionDevice::new(&bus));
+    let mut person_sensor = PersonSensorBuilder::new_standby(i2c, true)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(
+        person_sensor
+            .capture_once(&mut common::NoopDelay)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    critical_section::with(|cs| assert_eq!(bus.borrow_ref(cs).mode(), 0));
+}
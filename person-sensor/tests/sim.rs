@@ -0,0 +1,311 @@
+#![cfg(feature = "sim")]
+
+mod common;
+use common::NoopDelay;
+use person_sensor::{
+    calibration::CalibrationConfig,
+    sim::{SimCommand, SimulatedSensor},
+    CaptureWait, Face, PersonID, PersonSensorBuilder, Recognition,
+};
+
+fn face(left: u8, recognition: Recognition) -> Face {
+    Face {
+        box_confidence: 90,
+        box_left: left,
+        box_top: 10,
+        box_right: left + 50,
+        box_bottom: 60,
+        recognition,
+        is_facing: true,
+    }
+}
+
+fn identified(id: u8, confidence: u8) -> Recognition {
+    Recognition::Identified {
+        id: PersonID::new(id).unwrap(),
+        confidence,
+    }
+}
+
+#[tokio::test]
+async fn records_commands_and_registers() {
+    let sim = SimulatedSensor::new();
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), false)
+        .build()
+        .await
+        .unwrap();
+    person_sensor.set_indicator(false).await.unwrap();
+    person_sensor.set_persist_ids(false).await.unwrap();
+    let _person_sensor = person_sensor.into_standby_mode().await.unwrap();
+
+    assert_eq!(
+        sim.commands(),
+        [
+            SimCommand::SetMode(1),
+            SimCommand::EnableIdModel(false),
+            SimCommand::SetIndicator(false),
+            SimCommand::PersistIds(false),
+            SimCommand::SetMode(0),
+        ]
+    );
+    let registers = sim.registers();
+    assert_eq!(registers.mode, 0);
+    assert!(!registers.id_model_enabled);
+    assert!(!registers.indicator_enabled);
+    assert!(!registers.persist_ids);
+}
+
+#[tokio::test]
+async fn continuous_reads_follow_the_script() {
+    let sim = SimulatedSensor::new();
+    sim.push_scenes([
+        &[face(0, Recognition::Unknown)][..],
+        &[],
+        &[
+            face(0, Recognition::Unknown),
+            face(100, Recognition::NotLargestFace),
+        ],
+    ]);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+
+    let mut counts = Vec::new();
+    for _ in 0..4 {
+        counts.push(person_sensor.get_detections().await.unwrap().len());
+    }
+    // The last scene is repeated once the script runs out
+    assert_eq!(counts, [1, 0, 2, 2]);
+    assert_eq!(sim.scenes_remaining(), 0);
+    // Including the builder's probe
+    assert_eq!(sim.reads(), 5);
+}
+
+#[tokio::test]
+async fn standby_reads_return_the_last_capture() {
+    let sim = SimulatedSensor::new();
+    sim.push_scenes([&[face(0, Recognition::Unknown)][..], &[]]);
+    let mut person_sensor = PersonSensorBuilder::new_standby(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor.capture_once(&mut NoopDelay).await.unwrap();
+    assert_eq!(detections.len(), 1);
+    let person_sensor = person_sensor.into_continuous_mode().await.unwrap();
+    let mut person_sensor = person_sensor.into_standby_mode().await.unwrap();
+    assert_eq!(sim.frames(), 1);
+
+    let detections = person_sensor.capture_once(&mut NoopDelay).await.unwrap();
+    assert!(detections.is_empty());
+    assert_eq!(sim.frames(), 2);
+}
+
+#[tokio::test]
+async fn reads_during_inference_return_the_previous_frame() {
+    let sim = SimulatedSensor::new();
+    sim.set_inference_latency_ms(100);
+    sim.push_scene(&[face(0, Recognition::Unknown)]);
+    let mut person_sensor = PersonSensorBuilder::new_standby(sim.bus(), true)
+        .with_capture_wait(CaptureWait::Delay { ms: 50 })
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor.capture_once(&mut sim.delay()).await.unwrap();
+    assert!(detections.is_empty());
+    // Requesting another capture restarts inference, so the results are never ready
+    let detections = person_sensor.capture_once(&mut sim.delay()).await.unwrap();
+    assert!(detections.is_empty());
+    assert_eq!(sim.frames(), 0);
+    assert_eq!(sim.now_ms(), 100);
+}
+
+#[tokio::test]
+async fn capture_completes_after_inference() {
+    let sim = SimulatedSensor::new();
+    sim.set_inference_latency_ms(100);
+    sim.push_scene(&[face(0, Recognition::Unknown)]);
+    let mut person_sensor = PersonSensorBuilder::new_standby(sim.bus(), true)
+        .with_capture_wait(CaptureWait::Delay { ms: 100 })
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor.capture_once(&mut sim.delay()).await.unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(sim.frames(), 1);
+    assert_eq!(sim.now_ms(), 100);
+}
+
+#[tokio::test]
+async fn interrupt_rises_when_a_capture_with_faces_completes() {
+    let sim = SimulatedSensor::new();
+    sim.set_inference_latency_ms(100);
+    sim.push_scenes([
+        &[face(0, Recognition::Unknown)][..],
+        &[face(0, Recognition::Unknown)],
+    ]);
+    let mut person_sensor = PersonSensorBuilder::new_standby(sim.bus(), true)
+        .with_interrupt(sim.interrupt())
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor
+        .capture_once_with_interrupt(&mut sim.delay(), 500)
+        .await
+        .unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(sim.now_ms(), 100);
+    assert!(sim.interrupt_high());
+
+    // The pin is still high, so only the next frame's edge completes the capture
+    let detections = person_sensor
+        .capture_once_with_interrupt(&mut sim.delay(), 500)
+        .await
+        .unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(sim.now_ms(), 200);
+    assert_eq!(sim.frames(), 2);
+
+    // The last scene is repeated once the script runs out, so clear it with an empty one
+    sim.push_scene(&[]);
+    let detections = person_sensor
+        .capture_once_with_interrupt(&mut sim.delay(), 500)
+        .await
+        .unwrap();
+    assert!(detections.is_empty());
+    assert_eq!(sim.now_ms(), 700);
+    assert!(!sim.interrupt_high());
+}
+
+#[tokio::test]
+async fn uncalibrated_ids_are_not_reported() {
+    let sim = SimulatedSensor::new();
+    sim.push_scene(&[face(0, identified(3, 80))]);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor.get_detections().await.unwrap();
+    assert_eq!(detections[0].recognition, Recognition::Unknown);
+
+    sim.calibrate(PersonID::new(3).unwrap());
+    let detections = person_sensor.get_detections().await.unwrap();
+    assert_eq!(detections[0].recognition, identified(3, 80));
+}
+
+#[tokio::test]
+async fn disabled_id_model_reports_no_ids() {
+    let sim = SimulatedSensor::new();
+    sim.calibrate(PersonID::new(1).unwrap());
+    sim.push_scene(&[face(0, identified(1, 80))]);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), false)
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor.get_detections().await.unwrap();
+    assert_eq!(detections[0].id(), None);
+}
+
+#[tokio::test]
+async fn labels_the_next_face() {
+    let sim = SimulatedSensor::new();
+    let id = PersonID::new(2).unwrap();
+    sim.push_scenes([
+        &[face(0, Recognition::Unknown)][..],
+        &[face(0, Recognition::Unknown)],
+        &[face(0, identified(2, 90))],
+    ]);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+
+    let config = CalibrationConfig {
+        frame_interval_ms: 0,
+        required_matches: 1,
+        ..Default::default()
+    };
+    person_sensor
+        .calibrate(id, &config, &mut NoopDelay)
+        .await
+        .unwrap();
+    assert!(sim.is_calibrated(id));
+    assert_eq!(sim.pending_label(), None);
+}
+
+#[tokio::test]
+async fn label_is_discarded_without_a_face() {
+    let sim = SimulatedSensor::new();
+    let id = PersonID::new(5).unwrap();
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+
+    person_sensor.label_next_id(id).await.unwrap();
+    assert_eq!(sim.pending_label(), Some(id));
+    person_sensor.get_detections().await.unwrap();
+    assert_eq!(sim.pending_label(), Some(id));
+    person_sensor.get_detections().await.unwrap();
+    assert_eq!(sim.pending_label(), None);
+    assert!(!sim.is_calibrated(id));
+}
+
+#[tokio::test]
+async fn erase_forgets_calibrations() {
+    let sim = SimulatedSensor::new();
+    let id = PersonID::new(0).unwrap();
+    sim.calibrate(id);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+
+    person_sensor.erase_ids().await.unwrap();
+    assert!(!sim.is_calibrated(id));
+}
+
+#[tokio::test]
+async fn interrupt_follows_detections() {
+    let sim = SimulatedSensor::new();
+    sim.push_scenes([&[][..], &[face(0, Recognition::Unknown)]]);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+        .with_interrupt(sim.interrupt())
+        .build()
+        .await
+        .unwrap();
+
+    sim.advance();
+    assert!(!sim.interrupt_high());
+
+    let (result, ()) = tokio::join!(person_sensor.wait_for_person(), async {
+        tokio::task::yield_now().await;
+        sim.advance();
+    });
+    result.unwrap();
+    assert!(sim.interrupt_high());
+
+    // The frame produced by advancing is the one read next
+    let detections = person_sensor.get_detections().await.unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(sim.frames(), 2);
+
+    let _person_sensor = person_sensor.into_standby_mode().await.unwrap();
+    assert!(!sim.interrupt_high());
+}
+
+#[tokio::test]
+async fn other_addresses_are_not_acknowledged() {
+    use embedded_hal_async::i2c::I2c;
+
+    let sim = SimulatedSensor::new();
+    let mut bus = sim.bus();
+    assert!(bus.write(0x30, &[0x01, 0x00]).await.is_err());
+    assert!(sim.commands().is_empty());
+}
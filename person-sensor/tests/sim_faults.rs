@@ -0,0 +1,276 @@
+#![cfg(feature = "sim")]
+
+mod common;
+use common::{MockClock, MockDelay, NoopDelay};
+use person_sensor::{
+    command::{Command, CommandExecutor},
+    power::{PollError, PowerConfig, PowerManager, PowerState},
+    sim::{SimError, SimFault, SimulatedSensor},
+    CaptureWait, Face, PersonID, PersonSensorBuilder, PersonSensorMode, ProbeError, ReadError,
+    Recognition,
+};
+
+fn face(left: u8) -> Face {
+    Face {
+        box_confidence: 90,
+        box_left: left,
+        box_top: 10,
+        box_right: left + 50,
+        box_bottom: 60,
+        recognition: Recognition::Unknown,
+        is_facing: true,
+    }
+}
+
+#[tokio::test]
+async fn nack_on_nth_transaction() {
+    let sim = SimulatedSensor::new();
+    sim.push_scene(&[face(0)]);
+    // The builder uses the first three transactions
+    sim.inject_fault(4, SimFault::Nack);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+
+    assert!(person_sensor.get_detections().await.is_ok());
+    assert_eq!(
+        person_sensor.get_detections().await,
+        Err(ReadError::I2CError(SimError::Nack))
+    );
+    assert!(person_sensor.get_detections().await.is_ok());
+    assert_eq!(sim.transactions(), 6);
+}
+
+#[tokio::test]
+async fn nacked_probe_fails_build() {
+    let sim = SimulatedSensor::new();
+    sim.inject_next(SimFault::Nack);
+    let result = PersonSensorBuilder::new_standby(sim.bus(), true)
+        .build()
+        .await;
+    assert_eq!(result.err(), Some(ProbeError::NotDetected));
+    // The sensor was left unconfigured
+    assert!(sim.commands().is_empty());
+}
+
+#[tokio::test]
+async fn nacked_writes_have_no_effect() {
+    let sim = SimulatedSensor::new();
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+
+    sim.inject_next(SimFault::Nack);
+    assert_eq!(
+        person_sensor.set_indicator(false).await,
+        Err(SimError::Nack)
+    );
+    assert!(sim.registers().indicator_enabled);
+    assert_eq!(sim.commands().len(), 2);
+}
+
+#[tokio::test]
+async fn corrupted_checksum() {
+    let sim = SimulatedSensor::new();
+    sim.push_scene(&[face(0)]);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+
+    sim.inject_next(SimFault::CorruptChecksum);
+    assert_eq!(
+        person_sensor.get_detections().await,
+        Err(ReadError::ChecksumMismatch)
+    );
+    assert_eq!(person_sensor.get_detections().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn truncated_read() {
+    let sim = SimulatedSensor::new();
+    sim.push_scene(&[face(0)]);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+
+    sim.inject_next(SimFault::TruncatedRead { len: 20 });
+    assert_eq!(
+        person_sensor.get_detections().await,
+        Err(ReadError::ChecksumMismatch)
+    );
+}
+
+#[tokio::test]
+async fn wrong_face_count() {
+    let sim = SimulatedSensor::new();
+    sim.push_scene(&[face(0), face(100)]);
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+
+    sim.inject_next(SimFault::WrongFaceCount(1));
+    assert_eq!(person_sensor.get_detections().await.unwrap().len(), 1);
+
+    // Records beyond the faces in view are empty
+    sim.inject_next(SimFault::WrongFaceCount(3));
+    let detections = person_sensor.get_detections().await.unwrap();
+    assert_eq!(detections.len(), 3);
+    assert_eq!(detections[2].box_confidence, 0);
+
+    // Counts beyond the frame capacity are limited to it
+    sim.inject_next(SimFault::WrongFaceCount(u8::MAX));
+    assert_eq!(person_sensor.get_detections().await.unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn until_changed_retries_corrupted_frames() {
+    let sim = SimulatedSensor::new();
+    sim.push_scene(&[face(0)]);
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+    let mut person_sensor = PersonSensorBuilder::new_standby(sim.bus(), true)
+        .with_capture_wait(CaptureWait::UntilChanged {
+            interval_ms: 50,
+            timeout_ms: 1_000,
+        })
+        .build()
+        .await
+        .unwrap();
+
+    // The first read after requesting the capture
+    sim.inject_fault(sim.transactions() + 2, SimFault::CorruptChecksum);
+    let detections = person_sensor.capture_once(&mut delay).await.unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(clock.now_ms(), 100);
+}
+
+#[tokio::test]
+async fn stuck_low_interrupt_times_out() {
+    let sim = SimulatedSensor::new();
+    sim.push_scene(&[face(0)]);
+    sim.set_interrupt_stuck(Some(false));
+    let clock = MockClock::default();
+    let mut delay = MockDelay::new(&clock);
+    let mut person_sensor = PersonSensorBuilder::new_standby(sim.bus(), true)
+        .with_interrupt(sim.interrupt())
+        .build()
+        .await
+        .unwrap();
+
+    let detections = person_sensor
+        .capture_once_with_interrupt(&mut delay, 500)
+        .await
+        .unwrap();
+    assert_eq!(detections.len(), 1);
+    assert_eq!(clock.now_ms(), 500);
+}
+
+#[tokio::test]
+async fn stuck_high_interrupt_reports_phantom_person() {
+    let sim = SimulatedSensor::new();
+    let mut person_sensor = PersonSensorBuilder::new_continuous(sim.bus(), true)
+        .with_interrupt(sim.interrupt())
+        .build()
+        .await
+        .unwrap();
+
+    sim.set_interrupt_stuck(Some(true));
+    person_sensor.wait_for_person().await.unwrap();
+    assert!(person_sensor.get_detections().await.unwrap().is_empty());
+
+    sim.set_interrupt_stuck(None);
+    assert!(!sim.interrupt_high());
+}
+
+#[tokio::test]
+async fn reboot_resets_registers() {
+    let sim = SimulatedSensor::new();
+    let persisted = PersonID::new(1).unwrap();
+    sim.calibrate(persisted);
+    let mut person_sensor = PersonSensorBuilder::new_standby(sim.bus(), false)
+        .build()
+        .await
+        .unwrap();
+    person_sensor.set_indicator(false).await.unwrap();
+
+    sim.reboot();
+    let registers = sim.registers();
+    assert_eq!(registers.mode, 1);
+    assert!(registers.indicator_enabled);
+    assert!(sim.is_calibrated(persisted));
+
+    person_sensor.set_persist_ids(false).await.unwrap();
+    sim.reboot();
+    assert!(!sim.is_calibrated(persisted));
+    assert_eq!(sim.reboots(), 2);
+}
+
+#[tokio::test]
+async fn reboot_during_transaction() {
+    let sim = SimulatedSensor::new();
+    let mut person_sensor = PersonSensorBuilder::new_standby(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+
+    sim.inject_next(SimFault::Reboot);
+    assert!(person_sensor.capture_once(&mut NoopDelay).await.is_err());
+    // The sensor is back in its default continuous mode
+    assert_eq!(sim.registers().mode, 1);
+    assert_eq!(sim.reboots(), 1);
+}
+
+#[tokio::test]
+async fn failed_mode_transition_is_retried() {
+    let sim = SimulatedSensor::new();
+    sim.push_scene(&[face(0)]);
+    let clock = MockClock::default();
+    let sensor = PersonSensorBuilder::new_standby(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+    let mut manager = PowerManager::new(sensor, || clock.now_ms(), PowerConfig::default());
+
+    // Capture, read, then switch to continuous mode
+    sim.inject_fault(sim.transactions() + 2, SimFault::Nack);
+    match manager.poll(&mut NoopDelay).await {
+        Err(PollError::Transition { faces, error }) => {
+            assert_eq!(faces.len(), 1);
+            assert_eq!(error, SimError::Nack);
+        }
+        result => panic!("unexpected result: {result:?}"),
+    }
+    assert_eq!(manager.state(), PowerState::Standby);
+    assert_eq!(manager.next_poll_ms(), 5_000);
+    assert_eq!(sim.registers().mode, 0);
+
+    assert_eq!(manager.poll(&mut NoopDelay).await.unwrap().len(), 1);
+    assert_eq!(manager.state(), PowerState::Continuous);
+    assert_eq!(sim.registers().mode, 1);
+}
+
+#[tokio::test]
+async fn failed_mode_command_keeps_mode() {
+    let sim = SimulatedSensor::new();
+    let sensor = PersonSensorBuilder::new_standby(sim.bus(), true)
+        .build()
+        .await
+        .unwrap();
+    let mut executor = CommandExecutor::from_standby(sensor);
+
+    sim.inject_next(SimFault::Nack);
+    let result = executor
+        .execute(
+            Command::SetMode(PersonSensorMode::Continuous),
+            &mut NoopDelay,
+        )
+        .await;
+    assert_eq!(result, Err(ReadError::I2CError(SimError::Nack)));
+    assert_eq!(executor.mode(), PersonSensorMode::Standby);
+    assert_eq!(sim.registers().mode, 0);
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use common::MockClock;
+use person_sensor::{
+    smooth::{BoxSmoother, SmoothedBox, Smoothing, SmoothingConfig},
+    tracker::TrackerConfig,
+    BoundingBox, Face, Recognition,
+};
+
+/// A 40 by 40 face centered on `(x, y)`.
+fn face_at(x: u8, y: u8) -> Face {
+    Face {
+        box_confidence: 90,
+        box_left: x - 20,
+        box_top: y - 20,
+        box_right: x + 20,
+        box_bottom: y + 20,
+        recognition: Recognition::Unknown,
+        is_facing: true,
+    }
+}
+
+const TRACKER: TrackerConfig = TrackerConfig {
+    max_distance: 30,
+    max_missed_frames: 1,
+};
+
+const FRAME_MS: u64 = 100;
+
+/// Feeds a single person through the smoother, one frame every [`FRAME_MS`].
+fn smooth(
+    clock: &MockClock,
+    smoother: &mut BoxSmoother<impl person_sensor::Clock, 4>,
+    path: impl IntoIterator<Item = (u8, u8)>,
+) -> Vec<SmoothedBox> {
+    path.into_iter()
+        .map(|(x, y)| {
+            clock.advance_ms(FRAME_MS);
+            smoother.update(&[face_at(x, y)])[0]
+        })
+        .collect()
+}
+
+/// Alternates between two positions, 6 units apart.
+fn jitter(frames: usize) -> impl Iterator<Item = (u8, u8)> {
+    (0..frames).map(|frame| {
+        if frame % 2 == 0 {
+            (97, 100)
+        } else {
+            (103, 100)
+        }
+    })
+}
+
+#[test]
+fn ema_reduces_jitter() {
+    let clock = MockClock::default();
+    let config = SmoothingConfig {
+        smoothing: Smoothing::Ema { alpha: 0.2 },
+        tracker: TRACKER,
+    };
+    let mut smoother = BoxSmoother::<_, 4>::new(|| clock.now_ms(), config);
+
+    let boxes = smooth(&clock, &mut smoother, jitter(20));
+    for smoothed in &boxes[10..] {
+        assert!((smoothed.x - 100.0).abs() < 2.0, "{smoothed:?}");
+        assert_eq!(smoothed.width, 40.0);
+    }
+}
+
+#[test]
+fn kalman_reduces_jitter() {
+    let clock = MockClock::default();
+    let mut smoother = BoxSmoother::<_, 4>::new(|| clock.now_ms(), SmoothingConfig::default());
+
+    let boxes = smooth(&clock, &mut smoother, jitter(30));
+    for smoothed in &boxes[10..] {
+        assert!((smoothed.x - 100.0).abs() < 2.0, "{smoothed:?}");
+        assert!(smoothed.velocity_x.abs() < 20.0, "{smoothed:?}");
+    }
+}
+
+#[test]
+fn kalman_estimates_velocity() {
+    let clock = MockClock::default();
+    let mut smoother = BoxSmoother::<_, 4>::new(|| clock.now_ms(), SmoothingConfig::default());
+
+    // 5 units a frame, or 50 units a second
+    let boxes = smooth(&clock, &mut smoother, (0..20).map(|i| (40 + i * 5, 100)));
+    let last = boxes.last().unwrap();
+    assert!((last.velocity_x - 50.0).abs() < 5.0, "{last:?}");
+    assert!(last.velocity_y.abs() < 1.0, "{last:?}");
+    assert!((last.x - 135.0).abs() < 1.0, "{last:?}");
+
+    // Half a frame later, the person should have moved on by half as much
+    clock.advance_ms(FRAME_MS / 2);
+    let predicted = smoother.predict(last.track).unwrap();
+    assert!((predicted.x - 137.5).abs() < 1.0, "{predicted:?}");
+    assert_eq!(smoother.get(last.track).unwrap(), *last);
+}
+
+#[test]
+fn ema_estimates_velocity() {
+    let clock = MockClock::default();
+    let config = SmoothingConfig {
+        smoothing: Smoothing::Ema { alpha: 0.5 },
+        tracker: TRACKER,
+    };
+    let mut smoother = BoxSmoother::<_, 4>::new(|| clock.now_ms(), config);
+
+    let boxes = smooth(&clock, &mut smoother, (0..20).map(|i| (100, 40 + i * 5)));
+    let last = boxes.last().unwrap();
+    assert!((last.velocity_y - 50.0).abs() < 1.0, "{last:?}");
+}
+
+#[test]
+fn people_are_smoothed_separately() {
+    let clock = MockClock::default();
+    let mut smoother = BoxSmoother::<_, 4>::new(
+        || clock.now_ms(),
+        SmoothingConfig {
+            tracker: TRACKER,
+            ..SmoothingConfig::default()
+        },
+    );
+
+    smoother.update(&[face_at(50, 100), face_at(200, 100)]);
+    clock.advance_ms(FRAME_MS);
+    let boxes = smoother.update(&[face_at(52, 100), face_at(198, 100)]);
+    assert_eq!(boxes.len(), 2);
+    assert_ne!(boxes[0].track, boxes[1].track);
+    assert!(boxes[0].velocity_x > 0.0);
+    assert!(boxes[1].velocity_x < 0.0);
+
+    // Missed people keep their estimate until their track is lost
+    let missing = boxes[1].track;
+    clock.advance_ms(FRAME_MS);
+    assert_eq!(smoother.update(&[face_at(54, 100)]).len(), 1);
+    assert!(smoother.get(missing).is_some());
+    clock.advance_ms(FRAME_MS);
+    smoother.update(&[face_at(56, 100)]);
+    assert!(smoother.get(missing).is_none());
+    assert_eq!(smoother.boxes().count(), 1);
+}
+
+#[test]
+fn rounds_and_clips_boxes() {
+    let clock = MockClock::default();
+    let mut smoother = BoxSmoother::<_, 4>::new(|| clock.now_ms(), SmoothingConfig::default());
+
+    let smoothed = SmoothedBox {
+        x: 10.4,
+        y: 250.0,
+        width: 30.0,
+        height: 20.0,
+        ..smoother.update(&[face_at(100, 100)])[0]
+    };
+    assert_eq!(
+        smoothed.bounding_box(),
+        BoundingBox {
+            left: 0,
+            top: 240,
+            right: 25,
+            bottom: 255,
+        }
+    );
+}
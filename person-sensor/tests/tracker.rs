@@ -0,0 +1,109 @@
+use person_sensor::{
+    tracker::{Tracker, TrackerConfig},
+    Face, Recognition,
+};
+
+/// A 20 by 20 face centered on `(x, y)`.
+fn face_at(x: u8, y: u8) -> Face {
+    Face {
+        box_confidence: 90,
+        box_left: x - 10,
+        box_top: y - 10,
+        box_right: x + 10,
+        box_bottom: y + 10,
+        recognition: Recognition::Unknown,
+        is_facing: true,
+    }
+}
+
+const CONFIG: TrackerConfig = TrackerConfig {
+    max_distance: 30,
+    max_missed_frames: 1,
+};
+
+#[test]
+fn follows_faces_across_frames() {
+    let mut tracker = Tracker::<4>::new(CONFIG);
+    tracker.update(&[face_at(50, 100), face_at(200, 100)]);
+    let left = tracker.tracks()[0].id();
+    let right = tracker.tracks()[1].id();
+    assert_ne!(left, right);
+
+    // The order of the faces in a frame doesn't matter
+    let lost = tracker.update(&[face_at(210, 105), face_at(60, 95)]);
+    assert!(lost.is_empty());
+    assert_eq!(tracker.get(left).unwrap().center(), (60, 95));
+    assert_eq!(tracker.get(right).unwrap().center(), (210, 105));
+    assert_eq!(tracker.get(left).unwrap().frames(), 2);
+}
+
+#[test]
+fn closest_pairs_are_matched_first() {
+    let mut tracker = Tracker::<4>::new(CONFIG);
+    tracker.update(&[face_at(100, 100), face_at(130, 100)]);
+    let first = tracker.tracks()[0].id();
+    let second = tracker.tracks()[1].id();
+
+    // The face at 116 is closer to the first track than the face at 80, but closer still to the
+    // second track
+    tracker.update(&[face_at(80, 100), face_at(116, 100)]);
+    assert_eq!(tracker.get(first).unwrap().center(), (80, 100));
+    assert_eq!(tracker.get(second).unwrap().center(), (116, 100));
+}
+
+#[test]
+fn distant_faces_start_new_tracks() {
+    let mut tracker = Tracker::<4>::new(CONFIG);
+    tracker.update(&[face_at(50, 50)]);
+    let first = tracker.tracks()[0].id();
+
+    tracker.update(&[face_at(150, 150)]);
+    assert_eq!(tracker.tracks().len(), 2);
+    assert!(!tracker.get(first).unwrap().is_visible());
+    assert_ne!(tracker.tracks()[1].id(), first);
+}
+
+#[test]
+fn tracks_survive_missed_frames() {
+    let mut tracker = Tracker::<4>::new(CONFIG);
+    tracker.update(&[face_at(100, 100)]);
+    let id = tracker.tracks()[0].id();
+
+    assert!(tracker.update(&[]).is_empty());
+    assert_eq!(tracker.get(id).unwrap().missed_frames(), 1);
+    tracker.update(&[face_at(105, 100)]);
+    assert!(tracker.get(id).unwrap().is_visible());
+
+    tracker.update(&[]);
+    let lost = tracker.update(&[]);
+    assert_eq!(lost.len(), 1);
+    assert_eq!(lost[0].id(), id);
+    assert_eq!(lost[0].frames(), 2);
+    assert!(tracker.tracks().is_empty());
+}
+
+#[test]
+fn new_faces_replace_missing_tracks_when_full() {
+    let mut tracker = Tracker::<2>::new(CONFIG);
+    tracker.update(&[face_at(50, 50), face_at(150, 150)]);
+    let missing = tracker.tracks()[0].id();
+
+    let lost = tracker.update(&[face_at(150, 150), face_at(50, 200)]);
+    assert_eq!(lost.len(), 1);
+    assert_eq!(lost[0].id(), missing);
+    assert_eq!(tracker.tracks().len(), 2);
+
+    // Faces are ignored while every track is visible
+    tracker.update(&[face_at(150, 150), face_at(50, 200), face_at(200, 50)]);
+    assert_eq!(tracker.tracks().len(), 2);
+}
+
+#[test]
+fn ids_are_not_reused() {
+    let mut tracker = Tracker::<4>::new(CONFIG);
+    tracker.update(&[face_at(100, 100)]);
+    let first = tracker.tracks()[0].id();
+    tracker.reset();
+    tracker.update(&[face_at(100, 100)]);
+    assert!(tracker.tracks()[0].id() > first);
+}
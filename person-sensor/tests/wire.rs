@@ -0,0 +1,148 @@
+use person_sensor::{
+    wire::{self, StreamDecoder, WireError, WireFrame, MAX_ENCODED_LEN, WIRE_VERSION},
+    Detections, Face, PersonID, Recognition,
+};
+
+fn face(left: u8, recognition: Recognition, is_facing: bool) -> Face {
+    Face {
+        box_confidence: 90,
+        box_left: left,
+        box_top: 0,
+        box_right: left + 20,
+        box_bottom: 20,
+        recognition,
+        is_facing,
+    }
+}
+
+fn frame() -> WireFrame {
+    let faces = [
+        face(
+            0,
+            Recognition::Identified {
+                id: PersonID::new(2).unwrap(),
+                confidence: 77,
+            },
+            true,
+        ),
+        face(30, Recognition::NotLargestFace, false),
+        face(60, Recognition::Unknown, true),
+        face(90, Recognition::NotLargestFace, false),
+    ];
+    WireFrame {
+        timestamp_ms: 0x0102_0300,
+        faces: Detections::from_slice(&faces).unwrap(),
+    }
+}
+
+fn encode(frame: &WireFrame) -> Vec<u8> {
+    let mut buffer = [0u8; MAX_ENCODED_LEN];
+    let len = wire::encode(frame, &mut buffer).unwrap();
+    buffer[..len].to_vec()
+}
+
+#[test]
+fn round_trip() {
+    let frame = frame();
+    let encoded = encode(&frame);
+
+    assert_eq!(encoded.last(), Some(&0));
+    assert!(!encoded[..encoded.len() - 1].contains(&0));
+    assert_eq!(wire::decode(&encoded), Ok(frame));
+}
+
+#[test]
+fn empty_frame() {
+    let frame = WireFrame {
+        timestamp_ms: 0,
+        faces: Detections::new(),
+    };
+    let encoded = encode(&frame);
+    assert_eq!(wire::decode(&encoded), Ok(frame));
+}
+
+#[test]
+fn buffer_too_small() {
+    let mut buffer = [0u8; 20];
+    assert_eq!(
+        wire::encode(&frame(), &mut buffer),
+        Err(WireError::BufferTooSmall)
+    );
+}
+
+#[test]
+fn corrupted_frame() {
+    let mut encoded = encode(&frame());
+    encoded[10] ^= 0x01;
+    if encoded[10] == 0 {
+        encoded[10] = 0x80;
+    }
+    assert_eq!(wire::decode(&encoded), Err(WireError::ChecksumMismatch));
+}
+
+#[test]
+fn truncated_frame() {
+    let encoded = encode(&frame());
+    assert!(wire::decode(&encoded[..encoded.len() - 4]).is_err());
+    assert_eq!(wire::decode(&[]), Err(WireError::InvalidLength));
+}
+
+#[test]
+fn unsupported_version() {
+    let mut encoded = encode(&frame());
+    // The first byte is the COBS code, followed by the version
+    encoded[1] = WIRE_VERSION + 1;
+    assert_eq!(
+        wire::decode(&encoded),
+        Err(WireError::UnsupportedVersion(WIRE_VERSION + 1))
+    );
+}
+
+#[test]
+fn zero_recognition_confidence_is_rejected() {
+    let mut frame = frame();
+    frame.faces[0].recognition = Recognition::Identified {
+        id: PersonID::new(2).unwrap(),
+        confidence: 0,
+    };
+    assert_eq!(wire::decode(&encode(&frame)), Err(WireError::InvalidFace));
+}
+
+#[test]
+fn stream_decoder_resynchronizes() {
+    let first = frame();
+    let second = WireFrame {
+        timestamp_ms: 5,
+        faces: Detections::new(),
+    };
+
+    // The tail of a frame, then two complete frames, one of which is corrupted
+    let mut stream = vec![0x12, 0x34];
+    stream.push(0);
+    stream.extend(encode(&first));
+    let mut corrupted = encode(&second);
+    corrupted[3] = 0x55;
+    stream.extend(corrupted);
+    stream.extend(encode(&second));
+
+    let mut decoder = StreamDecoder::new();
+    let results: Vec<_> = stream.iter().filter_map(|b| decoder.push(*b)).collect();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0], Ok(first));
+    assert!(results[1].is_err());
+    assert_eq!(results[2], Ok(second));
+}
+
+#[test]
+fn stream_decoder_handles_overflow() {
+    let mut decoder = StreamDecoder::new();
+    decoder.push(0);
+    for _ in 0..MAX_ENCODED_LEN * 2 {
+        assert_eq!(decoder.push(0x01), None);
+    }
+    assert_eq!(decoder.push(0), Some(Err(WireError::InvalidEncoding)));
+
+    let encoded = encode(&frame());
+    let results: Vec<_> = encoded.iter().filter_map(|b| decoder.push(*b)).collect();
+    assert_eq!(results, [Ok(frame())]);
+}
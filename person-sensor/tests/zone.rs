@@ -0,0 +1,154 @@
+use person_sensor::{
+    zone::{Polygon, Region, Zone, ZoneError, ZoneEvent, ZoneMonitor},
+    BoundingBox, Face, Recognition,
+};
+
+fn face(left: u8, top: u8, right: u8, bottom: u8) -> Face {
+    Face {
+        box_confidence: 90,
+        box_left: left,
+        box_top: top,
+        box_right: right,
+        box_bottom: bottom,
+        recognition: Recognition::Unknown,
+        is_facing: true,
+    }
+}
+
+fn rect(left: u8, top: u8, right: u8, bottom: u8) -> BoundingBox {
+    BoundingBox {
+        left,
+        top,
+        right,
+        bottom,
+    }
+}
+
+#[test]
+fn rect_overlap() {
+    let region = Region::Rect(rect(0, 0, 100, 100));
+    assert_eq!(region.overlap_percent(&rect(10, 10, 50, 50)), 100);
+    assert_eq!(region.overlap_percent(&rect(50, 0, 150, 100)), 50);
+    assert_eq!(region.overlap_percent(&rect(75, 75, 125, 125)), 25);
+    assert_eq!(region.overlap_percent(&rect(150, 150, 200, 200)), 0);
+}
+
+#[test]
+fn polygon_overlap() {
+    // A triangle covering the lower left half of the square from (0, 0) to (100, 100)
+    let region = Region::Polygon(Polygon::new(&[(0, 0), (100, 100), (0, 100)]).unwrap());
+    assert_eq!(region.overlap_percent(&rect(0, 0, 100, 100)), 50);
+    assert_eq!(region.overlap_percent(&rect(0, 60, 20, 80)), 100);
+    assert_eq!(region.overlap_percent(&rect(60, 0, 100, 40)), 0);
+}
+
+#[test]
+fn concave_polygon_overlap() {
+    // A U shape, open at the top
+    let region = Region::Polygon(
+        Polygon::new(&[
+            (0, 0),
+            (40, 0),
+            (40, 60),
+            (60, 60),
+            (60, 0),
+            (100, 0),
+            (100, 100),
+            (0, 100),
+        ])
+        .unwrap(),
+    );
+    assert_eq!(region.overlap_percent(&rect(40, 0, 60, 60)), 0);
+    assert_eq!(region.overlap_percent(&rect(40, 40, 60, 80)), 50);
+    assert!(!region.contains((50, 30)));
+    assert!(region.contains((20, 30)));
+}
+
+#[test]
+fn polygon_vertex_limits() {
+    assert_eq!(
+        Polygon::new(&[(0, 0), (10, 10)]),
+        Err(ZoneError::TooFewVertices)
+    );
+    assert_eq!(Polygon::new(&[(0, 0); 9]), Err(ZoneError::TooManyVertices));
+}
+
+#[test]
+fn minimum_overlap() {
+    let zone = Zone::rect(rect(0, 0, 100, 100), 60);
+    assert!(zone.contains(&face(10, 10, 50, 50)));
+    assert!(!zone.contains(&face(50, 0, 150, 100)));
+
+    // Any overlap is enough at 0
+    let zone = Zone::rect(rect(0, 0, 100, 100), 0);
+    assert!(zone.contains(&face(90, 90, 150, 150)));
+    assert!(!zone.contains(&face(150, 150, 200, 200)));
+}
+
+#[test]
+fn boxes_without_area_use_their_center() {
+    let zone = Zone::rect(rect(0, 0, 100, 100), 50);
+    assert!(zone.contains(&face(50, 50, 50, 50)));
+    assert!(!zone.contains(&face(150, 50, 150, 50)));
+}
+
+#[test]
+fn occupancy_and_events() {
+    let mut monitor = ZoneMonitor::<2>::new();
+    let desk = monitor.add(Zone::rect(rect(0, 0, 127, 255), 50)).unwrap();
+    let door = monitor
+        .add(Zone::polygon(&[(128, 0), (255, 0), (255, 255), (128, 255)], 50).unwrap())
+        .unwrap();
+    assert_eq!(
+        monitor.add(Zone::rect(rect(0, 0, 10, 10), 0)),
+        Err(ZoneError::Full)
+    );
+
+    let events = monitor.update(&[face(10, 10, 60, 60), face(20, 100, 70, 150)]);
+    assert_eq!(
+        events,
+        [ZoneEvent::Entered {
+            zone: desk,
+            people: 2,
+            occupancy: 2
+        }]
+    );
+    assert_eq!(monitor.occupancy(desk), 2);
+    assert_eq!(monitor.occupancy(door), 0);
+
+    // One person walks over to the door
+    let events = monitor.update(&[face(10, 10, 60, 60), face(180, 100, 230, 150)]);
+    assert_eq!(
+        events,
+        [
+            ZoneEvent::Exited {
+                zone: desk,
+                people: 1,
+                occupancy: 1
+            },
+            ZoneEvent::Entered {
+                zone: door,
+                people: 1,
+                occupancy: 1
+            },
+        ]
+    );
+
+    // Nothing changes
+    let events = monitor.update(&[face(12, 10, 62, 60), face(182, 100, 232, 150)]);
+    assert!(events.is_empty());
+
+    monitor.reset();
+    assert_eq!(monitor.occupancy(desk), 0);
+}
+
+#[test]
+fn faces_can_be_in_several_zones() {
+    let mut monitor = ZoneMonitor::<2>::new();
+    let left = monitor.add(Zone::rect(rect(0, 0, 100, 255), 0)).unwrap();
+    let right = monitor.add(Zone::rect(rect(80, 0, 255, 255), 0)).unwrap();
+
+    monitor.update(&[face(70, 10, 110, 50)]);
+    assert_eq!(monitor.occupancy(left), 1);
+    assert_eq!(monitor.occupancy(right), 1);
+}